@@ -0,0 +1,10 @@
+#[allow(clippy::module_inception)]
+pub mod board_state;
+pub(crate) mod bitboard;
+pub mod fen;
+pub mod game_status;
+pub mod move_generation;
+pub mod perft;
+pub mod san;
+pub mod uci;
+pub mod zobrist;
@@ -0,0 +1,253 @@
+use crate::{ChessBoardPosition, ChessColors, ChessPieces, PositionedChessPiece};
+use std::fmt;
+
+/// A set of squares with one bit per square, a1 being the lowest bit and h8 the highest.
+pub(crate) type Bitboard = u64;
+
+const KING_OFFSETS: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [(2, 1), (1, 2), (-1, 2), (-2, 1), (-2, -1), (-1, -2), (1, -2), (2, -1)];
+
+/// For every square, the squares a king on it attacks.
+pub(crate) const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+/// For every square, the squares a knight on it attacks.
+pub(crate) const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+/// For both colors and every square, the squares a pawn of that color on it attacks.
+pub(crate) const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [leaper_attacks(&[(1, -1), (1, 1)]), leaper_attacks(&[(-1, -1), (-1, 1)])];
+
+const fn leaper_attacks(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut index = 0;
+        while index < offsets.len() {
+            let row = (square / 8) as i32 + offsets[index].0;
+            let column = (square % 8) as i32 + offsets[index].1;
+            if row >= 0 && row < 8 && column >= 0 && column < 8 {
+                attacks[square] |= 1 << (row * 8 + column);
+            }
+            index += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+/// The (row, column) steps of the sliding directions, the first four increasing the square index.
+const DIRECTIONS: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (1, -1), (-1, 0), (-1, -1), (0, -1), (-1, 1)];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 2, 4, 6];
+const BISHOP_DIRECTIONS: [usize; 4] = [1, 3, 5, 7];
+
+/// For every direction and square, the squares from there to the edge of the board, not including the square itself.
+const RAYS: [[Bitboard; 64]; 8] = rays();
+
+const fn rays() -> [[Bitboard; 64]; 8] {
+    let mut rays = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let mut square = 0;
+        while square < 64 {
+            let mut row = (square / 8) as i32 + DIRECTIONS[direction].0;
+            let mut column = (square % 8) as i32 + DIRECTIONS[direction].1;
+            while row >= 0 && row < 8 && column >= 0 && column < 8 {
+                rays[direction][square] |= 1 << (row * 8 + column);
+                row += DIRECTIONS[direction].0;
+                column += DIRECTIONS[direction].1;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    rays
+}
+
+/// The squares a sliding piece attacks in one direction, up to and including the first blocker.
+fn ray_attacks(direction: usize, index: usize, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[direction][index];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let first_blocker = if direction < 4 { blockers.trailing_zeros() } else { 63 - blockers.leading_zeros() };
+    ray ^ RAYS[direction][first_blocker as usize]
+}
+
+pub(crate) fn rook_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    ROOK_DIRECTIONS.iter().fold(0, |attacks, &direction| attacks | ray_attacks(direction, index, occupied))
+}
+
+pub(crate) fn bishop_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    BISHOP_DIRECTIONS.iter().fold(0, |attacks, &direction| attacks | ray_attacks(direction, index, occupied))
+}
+
+/// The squares strictly between the two squares, or None if they do not share a row, a column or a diagonal.
+pub(crate) fn squares_between(index_1: usize, index_2: usize) -> Option<Bitboard> {
+    let direction = (0..8).find(|&direction| RAYS[direction][index_1] & (1 << index_2) != 0)?;
+    Some(RAYS[direction][index_1] & !RAYS[direction][index_2] & !(1 << index_2))
+}
+
+pub(crate) fn square_index(position: ChessBoardPosition) -> usize {
+    position.row as usize * 8 + position.column as usize
+}
+
+/// The index of the square, or None if it lies outside of the board.
+pub(crate) fn checked_square_index(position: ChessBoardPosition) -> Option<usize> {
+    (position.row < 8 && position.column < 8).then(|| square_index(position))
+}
+
+pub(crate) fn square_bit(position: ChessBoardPosition) -> Bitboard {
+    1 << square_index(position)
+}
+
+pub(crate) fn position_from_index(index: usize) -> ChessBoardPosition {
+    ChessBoardPosition { row: (index / 8) as u8, column: (index % 8) as u8 }
+}
+
+pub(crate) fn color_index(color: ChessColors) -> usize {
+    match color {
+        ChessColors::White => 0,
+        ChessColors::Black => 1,
+    }
+}
+
+pub(crate) fn piece_index(piece: ChessPieces) -> usize {
+    match piece {
+        ChessPieces::Pawn => 0,
+        ChessPieces::Knight => 1,
+        ChessPieces::Bishop => 2,
+        ChessPieces::Rook => 3,
+        ChessPieces::Queen => 4,
+        ChessPieces::King => 5,
+    }
+}
+
+/// Iterates over the squares of a bitboard from a1 to h8.
+pub(crate) struct Squares(pub(crate) Bitboard);
+
+impl Iterator for Squares {
+    type Item = ChessBoardPosition;
+
+    fn next(&mut self) -> Option<ChessBoardPosition> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(position_from_index(index))
+    }
+}
+
+/// Where the pieces stand, stored twice: as bitboards per color and per piece type for set operations, and as a mailbox for looking up the piece on a square.
+#[derive( Clone)]
+pub(crate) struct PiecePlacement {
+    colors: [Bitboard; 2],
+    piece_types: [Bitboard; 6],
+    occupied: Bitboard,
+    mailbox: [Option<(ChessPieces, ChessColors)>; 64],
+}
+
+impl PiecePlacement {
+    pub(crate) fn from_pieces(pieces: &[PositionedChessPiece]) -> PiecePlacement {
+        let mut placement = PiecePlacement { colors: [0; 2], piece_types: [0; 6], occupied: 0, mailbox: [None; 64] };
+        for piece in pieces {
+            placement.put(piece.piece, piece.color, piece.position);
+        }
+        placement
+    }
+
+    /// Puts a piece on an empty square of the board.
+    pub(crate) fn put(&mut self, piece: ChessPieces, color: ChessColors, position: ChessBoardPosition) {
+        debug_assert!(checked_square_index(position).is_some(), "{:?} is not on the board", position);
+        let bit = square_bit(position);
+        self.colors[color_index(color)] |= bit;
+        self.piece_types[piece_index(piece)] |= bit;
+        self.occupied |= bit;
+        self.mailbox[square_index(position)] = Some((piece, color));
+    }
+
+    /// Takes the piece on the square off the board and returns it.
+    pub(crate) fn remove(&mut self, position: ChessBoardPosition) -> Option<PositionedChessPiece> {
+        let (piece, color) = self.mailbox[checked_square_index(position)?].take()?;
+        let bit = square_bit(position);
+        self.colors[color_index(color)] &= !bit;
+        self.piece_types[piece_index(piece)] &= !bit;
+        self.occupied &= !bit;
+        Some(PositionedChessPiece { piece, color, position })
+    }
+
+    pub(crate) fn get(&self, position: ChessBoardPosition) -> Option<PositionedChessPiece> {
+        self.mailbox[checked_square_index(position)?].map(|(piece, color)| PositionedChessPiece { piece, color, position })
+    }
+
+    pub(crate) fn occupied(&self) -> Bitboard {
+        self.occupied
+    }
+
+    pub(crate) fn color(&self, color: ChessColors) -> Bitboard {
+        self.colors[color_index(color)]
+    }
+
+    pub(crate) fn pieces_of(&self, piece: ChessPieces, color: ChessColors) -> Bitboard {
+        self.piece_types[piece_index(piece)] & self.colors[color_index(color)]
+    }
+
+    /// All pieces, ordered by square from a1 to h8.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = PositionedChessPiece> + '_ {
+        Squares(self.occupied).map(|position| self.get(position).expect("occupied squares have a piece in the mailbox"))
+    }
+}
+
+/// Prints the pieces like a list of PositionedChessPiece instead of the raw bitboards.
+impl fmt::Debug for PiecePlacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::square;
+    use super::*;
+
+    #[test]
+    fn test_attack_tables() {
+        let squares = |bitboard: Bitboard| Squares(bitboard).map(|position| position.to_string()).collect::<Vec<String>>();
+        assert_eq!(squares(KNIGHT_ATTACKS[square_index(square("a1"))]), ["c2", "b3"]);
+        assert_eq!(squares(KING_ATTACKS[square_index(square("h8"))]), ["g7", "h7", "g8"]);
+        assert_eq!(squares(PAWN_ATTACKS[0][square_index(square("e4"))]), ["d5", "f5"]);
+        assert_eq!(squares(PAWN_ATTACKS[1][square_index(square("a7"))]), ["b6"]);
+    }
+
+    #[test]
+    fn test_sliding_attacks() {
+        let squares = |bitboard: Bitboard| Squares(bitboard).map(|position| position.to_string()).collect::<Vec<String>>();
+        let occupied = square_bit(square("d2")) | square_bit(square("d6")) | square_bit(square("b4")) | square_bit(square("f6")) | square_bit(square("b2"));
+        assert_eq!(squares(rook_attacks(square_index(square("d4")), occupied)), ["d2", "d3", "b4", "c4", "e4", "f4", "g4", "h4", "d5", "d6"]);
+        assert_eq!(squares(bishop_attacks(square_index(square("d4")), occupied)), ["g1", "b2", "f2", "c3", "e3", "c5", "e5", "b6", "f6", "a7"]);
+        assert_eq!(squares(rook_attacks(square_index(square("h8")), 0)).len(), 14);
+        assert_eq!(squares(squares_between(square_index(square("h1")), square_index(square("b7"))).unwrap()), ["g2", "f3", "e4", "d5", "c6"]);
+        assert_eq!(squares(squares_between(square_index(square("e8")), square_index(square("e1"))).unwrap()).len(), 6);
+        assert_eq!(squares_between(square_index(square("a1")), square_index(square("b2"))), Some(0));
+        assert_eq!(squares_between(square_index(square("a1")), square_index(square("b3"))), None);
+    }
+
+    #[test]
+    fn test_put_and_remove() {
+        let mut placement = PiecePlacement::from_pieces(&[PositionedChessPiece { piece: ChessPieces::Rook, color: ChessColors::Black, position: square("h8") }]);
+        placement.put(ChessPieces::Knight, ChessColors::White, square("c3"));
+        assert_eq!(placement.occupied(), square_bit(square("c3")) | square_bit(square("h8")));
+        assert_eq!(placement.pieces_of(ChessPieces::Knight, ChessColors::White), square_bit(square("c3")));
+        assert_eq!(placement.pieces_of(ChessPieces::Knight, ChessColors::Black), 0);
+        assert_eq!(placement.iter().map(|piece| piece.position).collect::<Vec<_>>(), [square("c3"), square("h8")]);
+        let rook = placement.remove(square("h8")).unwrap();
+        assert_eq!(rook.piece, ChessPieces::Rook);
+        assert_eq!(placement.remove(square("h8")), None);
+        assert_eq!(placement.color(ChessColors::Black), 0);
+        assert_eq!(placement.get(square("c3")).map(|piece| piece.color), Some(ChessColors::White));
+        // Without the check, column 10 of row 0 would be read as c2.
+        placement.put(ChessPieces::Pawn, ChessColors::White, square("c2"));
+        assert_eq!(placement.get(ChessBoardPosition { row: 0, column: 10 }), None);
+        assert_eq!(placement.get(ChessBoardPosition { row: 8, column: 0 }), None);
+        assert_eq!(placement.remove(ChessBoardPosition { row: 0, column: 10 }), None);
+        assert_eq!(placement.get(square("c2")).map(|piece| piece.piece), Some(ChessPieces::Pawn));
+    }
+}
@@ -0,0 +1,916 @@
+use crate::board_state::bitboard::{bishop_attacks, color_index, rook_attacks, square_bit, square_index, squares_between, Bitboard, PiecePlacement, Squares, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+use crate::board_state::zobrist::default_zobrist_keys;
+use crate::{CastlingStateData, ChessBoardPosition, ChessColors, ChessMove, ChessPieces, MoveError, MoveOutcome, PositionedChessPiece, ZobristKeys};
+use std::sync::Arc;
+
+/// Everything that a move changes irreversibly, so that it can be taken back.
+#[derive( Clone, Debug)]
+pub(crate) struct MoveUndoData {
+    captured_piece: Option<PositionedChessPiece>,
+    white_castling_state: CastlingStateData,
+    black_castling_state: CastlingStateData,
+    halfmove_clock: i32,
+    move_counter: i32,
+    en_passant_target: Option<ChessBoardPosition>,
+    /// The Zobrist key of the position before the move.
+    pub(crate) zobrist_key: u64,
+}
+
+/// The full state of a game of chess: the pieces on the board, the side to move, the castling state of both sides and the moves played so far.
+#[derive( Clone, Debug)]
+pub struct ChessBoardState {
+    pub(crate) placement: PiecePlacement,
+    pub(crate) move_counter: i32,
+    pub(crate) to_move: ChessColors,
+    pub white_castling_state: CastlingStateData,
+    pub(crate) black_castling_state: CastlingStateData,
+    pub(crate) move_history: Vec<ChessMove>,
+    /// For every move in move_history, the data needed to undo it.
+    pub(crate) undo_history: Vec<MoveUndoData>,
+    pub(crate) halfmove_clock: i32,
+    pub(crate) en_passant_target: Option<ChessBoardPosition>,
+    /// The FEN the board was created from, or None if the game started from the standard starting position.
+    pub(crate) initial_fen: Option<String>,
+    pub(crate) zobrist_keys: Arc<ZobristKeys>,
+    pub(crate) zobrist_key: u64,
+}
+
+impl Default for ChessBoardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChessBoardState {
+    /// Creates a board in the standard starting position with white to move.
+    pub fn new() -> ChessBoardState {
+        let mut board = ChessBoardState {
+            placement: PiecePlacement::from_pieces(&[
+                PositionedChessPiece {
+                    piece: ChessPieces::King,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 4 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Queen,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 3 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Rook,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 0 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Rook,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 7 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Bishop,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 2 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Bishop,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 5 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Knight,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 1 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Knight,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 0, column: 6 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 0 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 1 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 2 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 3 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 4 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 5 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 6 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::White,
+                    position: ChessBoardPosition { row: 1, column: 7 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::King,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 4 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Queen,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 3 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Rook,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 0 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Rook,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 7 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Bishop,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 2 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Bishop,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 5 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Knight,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 1 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Knight,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 7, column: 6 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 0 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 1 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 2 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 3 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 4 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 5 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 6 },
+                },
+                PositionedChessPiece {
+                    piece: ChessPieces::Pawn,
+                    color: ChessColors::Black,
+                    position: ChessBoardPosition { row: 6, column: 7 },
+                },
+            ]),
+            move_counter: 0,
+            to_move: ChessColors::White,
+            white_castling_state: CastlingStateData {
+                rook_a_moved: false,
+                rook_h_moved: false,
+                king_moved: false,
+            },
+            black_castling_state: CastlingStateData {
+                rook_a_moved: false,
+                rook_h_moved: false,
+                king_moved: false,
+            },
+            move_history: vec![],
+            undo_history: vec![],
+            halfmove_clock: 0,
+            en_passant_target: None,
+            initial_fen: None,
+            zobrist_keys: default_zobrist_keys(),
+            zobrist_key: 0,
+        };
+        board.zobrist_key = board.compute_zobrist_key();
+        board
+    }
+
+    /// The pieces currently on the board, ordered by square from a1 to h8.
+    pub fn pieces(&self) -> impl Iterator<Item = PositionedChessPiece> + '_ {
+        self.placement.iter()
+    }
+
+    /// The side whose turn it is.
+    pub fn to_move(&self) -> ChessColors {
+        self.to_move
+    }
+
+    /// The moves performed on this board so far, oldest first.
+    pub fn move_history(&self) -> &[ChessMove] {
+        &self.move_history
+    }
+
+    /// The number of half moves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> i32 {
+        self.halfmove_clock
+    }
+
+    /// The FEN of the position the game started from, if it was not the standard starting position.
+    pub fn initial_fen(&self) -> Option<&str> {
+        self.initial_fen.as_deref()
+    }
+
+    /// The castling state of the given side.
+    pub fn castling_state(&self, side: ChessColors) -> CastlingStateData {
+        match side {
+            ChessColors::White => self.white_castling_state,
+            ChessColors::Black => self.black_castling_state,
+        }
+    }
+
+    /// The square skipped by the previous double pawn push, if any.
+    pub fn en_passant_target(&self) -> Option<ChessBoardPosition> {
+        self.en_passant_target
+    }
+
+    /// Returns true if the move is a pawn capturing en passant.
+    pub fn is_en_passant(&self, next_move: ChessMove) -> bool {
+        next_move.piece == ChessPieces::Pawn && next_move.from.column != next_move.to.column && self.en_passant_target == Some(next_move.to)
+    }
+
+    /// The square of the piece captured by the move, which differs from the target square for en passant.
+    fn get_captured_position(&self, next_move: ChessMove) -> ChessBoardPosition {
+        if self.is_en_passant(next_move) {
+            ChessBoardPosition { row: next_move.from.row, column: next_move.to.column }
+        } else {
+            next_move.to
+        }
+    }
+
+    /// Puts a piece on an empty square and updates the Zobrist key.
+    fn put_piece(&mut self, piece: ChessPieces, color: ChessColors, position: ChessBoardPosition) {
+        self.placement.put(piece, color, position);
+        self.toggle_zobrist_piece(piece, color, position);
+    }
+
+    /// Takes the piece on the square off the board and updates the Zobrist key.
+    fn remove_piece(&mut self, position: ChessBoardPosition) -> Option<PositionedChessPiece> {
+        let removed = self.placement.remove(position)?;
+        self.toggle_zobrist_piece(removed.piece, removed.color, position);
+        Some(removed)
+    }
+
+    fn move_castling_rook(&mut self, next_move: ChessMove) {
+        if let Some((rook_from, rook_to)) = Self::get_castling_rook_move(next_move) {
+            if self.remove_piece(rook_from).is_some() {
+                self.put_piece(ChessPieces::Rook, next_move.color, rook_to);
+            }
+        }
+    }
+
+    /// Applies the move if it is valid and describes what it did, otherwise leaves the board untouched.
+    pub fn perform_move(&mut self, next_move: ChessMove) -> Result<MoveOutcome, MoveError> {
+        if let Some(reason) = self.automatic_draw() {
+            return Err(MoveError::GameOver(reason));
+        }
+        self.check_pseudo_legal(next_move)?;
+        let is_en_passant = self.is_en_passant(next_move);
+        self.make_move(next_move);
+        if self.is_in_check(next_move.color) {
+            self.undo_move();
+            return Err(MoveError::LeavesKingInCheck(next_move));
+        }
+        let is_check = self.is_in_check(self.to_move);
+        let promotion = match self.get_piece_at(next_move.to) {
+            Some(piece) if piece.piece != next_move.piece => Some(piece.piece),
+            _ => None,
+        };
+        Ok(MoveOutcome {
+            captured: self.undo_history.last().and_then(|undo_data| undo_data.captured_piece),
+            is_check,
+            is_checkmate: is_check && !self.has_legal_move(),
+            is_castling: next_move.is_castling(),
+            is_en_passant,
+            promotion,
+        })
+    }
+
+    /// Takes back the last move and returns it, or None if there is no move to undo.
+    pub fn undo_move(&mut self) -> Option<ChessMove> {
+        let undo_data = self.undo_history.pop()?;
+        let last_move = self.move_history.pop()?;
+        self.zobrist_key ^= self.zobrist_state_key();
+        self.remove_piece(last_move.to);
+        self.put_piece(last_move.piece, last_move.color, last_move.from);
+        if let Some((rook_from, rook_to)) = Self::get_castling_rook_move(last_move) {
+            if self.remove_piece(rook_to).is_some() {
+                self.put_piece(ChessPieces::Rook, last_move.color, rook_from);
+            }
+        }
+        if let Some(captured_piece) = undo_data.captured_piece {
+            self.put_piece(captured_piece.piece, captured_piece.color, captured_piece.position);
+        }
+        self.white_castling_state = undo_data.white_castling_state;
+        self.black_castling_state = undo_data.black_castling_state;
+        self.halfmove_clock = undo_data.halfmove_clock;
+        self.en_passant_target = undo_data.en_passant_target;
+        self.move_counter = undo_data.move_counter;
+        self.to_move = last_move.color;
+        self.zobrist_key ^= self.zobrist_state_key();
+        Some(last_move)
+    }
+
+    /// Performs the move without any validation and records what is needed to undo it.
+    pub(crate) fn make_move(&mut self, next_move: ChessMove) {
+        let mut undo_data = MoveUndoData {
+            captured_piece: None,
+            white_castling_state: self.white_castling_state,
+            black_castling_state: self.black_castling_state,
+            halfmove_clock: self.halfmove_clock,
+            move_counter: self.move_counter,
+            en_passant_target: self.en_passant_target,
+            zobrist_key: self.zobrist_key,
+        };
+        self.zobrist_key ^= self.zobrist_state_key();
+        undo_data.captured_piece = self.remove_piece(self.get_captured_position(next_move));
+        self.remove_piece(next_move.from);
+        self.put_piece(next_move.promotion.unwrap_or(next_move.piece), next_move.color, next_move.to);
+        self.move_castling_rook(next_move);
+        self.move_counter = self.move_counter.saturating_add(1);
+        self.to_move = match self.to_move {
+            ChessColors::White => ChessColors::Black,
+            ChessColors::Black => ChessColors::White,
+        };
+        self.en_passant_target = if next_move.piece == ChessPieces::Pawn && (next_move.from.row as i32 - next_move.to.row as i32).abs() == 2 {
+            Some(ChessBoardPosition { row: (next_move.from.row + next_move.to.row) / 2, column: next_move.to.column })
+        } else {
+            None
+        };
+        self.update_half_move_clock(next_move.piece, undo_data.captured_piece.is_some());
+        self.update_castling_state_for_move(next_move);
+        self.zobrist_key ^= self.zobrist_state_key();
+        self.move_history.push(next_move);
+        self.undo_history.push(undo_data);
+    }
+
+    /// Plays the move, checks whether the own king is attacked and takes the move back.
+    pub(crate) fn leaves_king_in_check(&mut self, next_move: ChessMove) -> bool {
+        self.make_move(next_move);
+        let in_check = self.is_in_check(next_move.color);
+        self.undo_move();
+        in_check
+    }
+
+    /// A copy of the position without the move history, for looking ahead with make and undo.
+    pub(crate) fn copy_position(&self) -> ChessBoardState {
+        ChessBoardState {
+            placement: self.placement.clone(),
+            move_counter: self.move_counter,
+            to_move: self.to_move,
+            white_castling_state: self.white_castling_state,
+            black_castling_state: self.black_castling_state,
+            move_history: vec![],
+            undo_history: vec![],
+            halfmove_clock: self.halfmove_clock,
+            en_passant_target: self.en_passant_target,
+            initial_fen: None,
+            zobrist_keys: Arc::clone(&self.zobrist_keys),
+            zobrist_key: self.zobrist_key,
+        }
+    }
+
+    fn update_half_move_clock(&mut self, piece: ChessPieces, move_was_capture: bool) {
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        if piece == ChessPieces::Pawn {
+            self.halfmove_clock = 0;
+            return;
+        }
+        if move_was_capture {
+            self.halfmove_clock = 0;
+        }
+    }
+
+    /// Ends castling for a king move and for any move from or to a rook's home square.
+    fn update_castling_state_for_move(&mut self, next_move: ChessMove) {
+        if next_move.piece == ChessPieces::King {
+            match next_move.color {
+                ChessColors::White => self.white_castling_state.king_moved = true,
+                ChessColors::Black => self.black_castling_state.king_moved = true,
+            }
+        }
+        for square in [next_move.from, next_move.to] {
+            let castling_state = match square.row {
+                0 => &mut self.white_castling_state,
+                7 => &mut self.black_castling_state,
+                _ => continue,
+            };
+            match square.column {
+                0 => castling_state.rook_a_moved = true,
+                7 => castling_state.rook_h_moved = true,
+                _ => {},
+            }
+        }
+    }
+
+    fn get_king_position(&self, side: ChessColors) -> Option<ChessBoardPosition> {
+        Squares(self.placement.pieces_of(ChessPieces::King, side)).next()
+    }
+
+    pub(crate) fn get_piece_at(&self, position: ChessBoardPosition) -> Option<PositionedChessPiece> {
+        self.placement.get(position)
+    }
+
+    pub(crate) fn is_on_board(position: ChessBoardPosition) -> bool {
+        position.row < 8 && position.column < 8
+    }
+
+    /// The squares attacked by a rook, bishop or queen on the given square, empty for other pieces.
+    pub(crate) fn sliding_attacks(&self, piece: ChessPieces, position: ChessBoardPosition) -> Bitboard {
+        let index = square_index(position);
+        let occupied = self.placement.occupied();
+        match piece {
+            ChessPieces::Rook => rook_attacks(index, occupied),
+            ChessPieces::Bishop => bishop_attacks(index, occupied),
+            ChessPieces::Queen => rook_attacks(index, occupied) | bishop_attacks(index, occupied),
+            _ => 0,
+        }
+    }
+
+    /// Checks castling rights, empty squares between king and rook and that the king does not start in or pass through check.
+    fn is_castling_valid(&self, next_move: ChessMove) -> bool {
+        let home_row = match next_move.color {
+            ChessColors::White => 0,
+            ChessColors::Black => 7,
+        };
+        if !next_move.is_castling() || next_move.from != (ChessBoardPosition { row: home_row, column: 4 }) || next_move.to.row != home_row {
+            return false;
+        }
+        let castling_state = self.castling_state(next_move.color);
+        let kingside = next_move.to.column == 6;
+        let rook_moved = if kingside { castling_state.rook_h_moved } else { castling_state.rook_a_moved };
+        if castling_state.king_moved || rook_moved {
+            return false;
+        }
+        let rook_position = ChessBoardPosition { row: home_row, column: if kingside { 7 } else { 0 } };
+        match self.get_piece_at(rook_position) {
+            Some(piece) if piece.piece == ChessPieces::Rook && piece.color == next_move.color => {},
+            _ => return false,
+        }
+        if squares_between(square_index(next_move.from), square_index(rook_position)).is_none_or(|between| between & self.placement.occupied() != 0) {
+            return false;
+        }
+        let passed_square = ChessBoardPosition { row: home_row, column: (next_move.from.column + next_move.to.column) / 2 };
+        let opponent = next_move.color.opponent();
+        !self.is_square_attacked(next_move.from, opponent) && !self.is_square_attacked(passed_square, opponent)
+    }
+
+    /// For a castling move, returns the start and target square of the rook.
+    fn get_castling_rook_move(next_move: ChessMove) -> Option<(ChessBoardPosition, ChessBoardPosition)> {
+        if !next_move.is_castling() {
+            return None;
+        }
+        let row = next_move.from.row;
+        if next_move.to.column > next_move.from.column {
+            Some((ChessBoardPosition { row, column: 7 }, ChessBoardPosition { row, column: 5 }))
+        } else {
+            Some((ChessBoardPosition { row, column: 0 }, ChessBoardPosition { row, column: 3 }))
+        }
+    }
+
+    /// The en passant target square is only valid for the side that did not perform the double push, and only if the pushed pawn is still there.
+    fn is_en_passant_capture_possible(&self, next_move: ChessMove) -> bool {
+        if !self.is_en_passant(next_move) {
+            return false;
+        }
+        let capture_row = match next_move.color {
+            ChessColors::White => 5,
+            ChessColors::Black => 2,
+        };
+        if next_move.to.row != capture_row {
+            return false;
+        }
+        match self.get_piece_at(self.get_captured_position(next_move)) {
+            Some(piece) => piece.piece == ChessPieces::Pawn && piece.color != next_move.color,
+            None => false,
+        }
+    }
+
+    /// Checks the shape of the move, blocking pieces and that the target square holds no own piece.
+    fn is_move_geometry_valid(&self, next_move: ChessMove) -> bool {
+        if next_move.from == next_move.to {
+            return false;
+        }
+        if self.placement.color(next_move.color) & square_bit(next_move.to) != 0 {
+            return false;
+        }
+        let target = self.get_piece_at(next_move.to);
+        let row_diff = next_move.to.row as i32 - next_move.from.row as i32;
+        let col_diff = next_move.to.column as i32 - next_move.from.column as i32;
+        let target_bit = square_bit(next_move.to);
+        match next_move.piece {
+            ChessPieces::King => KING_ATTACKS[square_index(next_move.from)] & target_bit != 0 || self.is_castling_valid(next_move),
+            ChessPieces::Queen | ChessPieces::Rook | ChessPieces::Bishop => self.sliding_attacks(next_move.piece, next_move.from) & target_bit != 0,
+            ChessPieces::Knight => KNIGHT_ATTACKS[square_index(next_move.from)] & target_bit != 0,
+            ChessPieces::Pawn => {
+                let (direction, start_row) = match next_move.color {
+                    ChessColors::White => (1, 1),
+                    ChessColors::Black => (-1, 6),
+                };
+                if col_diff == 0 {
+                    if target.is_some() {
+                        return false;
+                    }
+                    if row_diff == direction {
+                        return true;
+                    }
+                    let passed_square = ChessBoardPosition { row: (next_move.from.row + next_move.to.row) / 2, column: next_move.from.column };
+                    row_diff == 2 * direction && next_move.from.row == start_row && self.get_piece_at(passed_square).is_none()
+                } else {
+                    col_diff.abs() == 1 && row_diff == direction && (target.is_some() || self.is_en_passant_capture_possible(next_move))
+                }
+            },
+        }
+    }
+
+    /// Returns true if the king of the given side is attacked.
+    pub fn is_in_check(&self, side: ChessColors) ->bool {
+        let king_position = self.get_king_position(side).expect("No king found");
+        self.is_square_attacked(king_position, side.opponent())
+    }
+
+    /// Returns true if any piece of the attacking side could capture on the given square.
+    fn is_square_attacked(&self, position: ChessBoardPosition, attacker: ChessColors) -> bool {
+        let index = square_index(position);
+        let pieces_of = |piece: ChessPieces| self.placement.pieces_of(piece, attacker);
+        // A pawn attacks the square if a pawn of the other color on the square would attack the pawn.
+        if PAWN_ATTACKS[color_index(attacker.opponent())][index] & pieces_of(ChessPieces::Pawn) != 0
+            || KNIGHT_ATTACKS[index] & pieces_of(ChessPieces::Knight) != 0
+            || KING_ATTACKS[index] & pieces_of(ChessPieces::King) != 0 {
+            return true;
+        }
+        // Likewise, a sliding piece attacks the square if it stands on a square that a piece of its kind on the square would attack.
+        let occupied = self.placement.occupied();
+        rook_attacks(index, occupied) & (pieces_of(ChessPieces::Rook) | pieces_of(ChessPieces::Queen)) != 0
+            || bishop_attacks(index, occupied) & (pieces_of(ChessPieces::Bishop) | pieces_of(ChessPieces::Queen)) != 0
+    }
+
+    /// Checks whether the move can be performed, including that the own king is not left in check.
+    pub fn is_move_valid(&self, next_move: ChessMove) -> bool {
+        self.validate_move(next_move).is_ok()
+    }
+
+    /// Like is_move_valid, but tells which rule an invalid move breaks.
+    pub fn validate_move(&self, next_move: ChessMove) -> Result<(), MoveError> {
+        if let Some(reason) = self.automatic_draw() {
+            return Err(MoveError::GameOver(reason));
+        }
+        self.check_pseudo_legal(next_move)?;
+        if self.copy_position().leaves_king_in_check(next_move) {
+            return Err(MoveError::LeavesKingInCheck(next_move));
+        }
+        Ok(())
+    }
+
+    /// Checks everything but the safety of the own king: the piece is on the start square and the move follows its movement rules.
+    pub(crate) fn is_move_pseudo_legal(&self, next_move: ChessMove) -> bool {
+        self.check_pseudo_legal(next_move).is_ok()
+    }
+
+    fn check_pseudo_legal(&self, next_move: ChessMove) -> Result<(), MoveError> {
+        for position in [next_move.from, next_move.to] {
+            if !Self::is_on_board(position) {
+                return Err(MoveError::SquareOffBoard(position));
+            }
+        }
+        match self.get_piece_at(next_move.from) {
+            None => return Err(MoveError::NoPieceOnSquare(next_move.from)),
+            Some(piece) if piece.piece != next_move.piece || piece.color != next_move.color => {
+                return Err(MoveError::PieceMismatch { position: piece.position, piece: piece.piece, color: piece.color });
+            },
+            Some(_) => {},
+        }
+        if next_move.color != self.to_move {
+            return Err(MoveError::WrongSideToMove(next_move.color));
+        }
+        let reaches_last_rank = next_move.piece == ChessPieces::Pawn && (next_move.to.row == 0 || next_move.to.row == 7);
+        match next_move.promotion {
+            None if reaches_last_rank => return Err(MoveError::MissingPromotion(next_move)),
+            Some(_) if !reaches_last_rank => return Err(MoveError::UnexpectedPromotion(next_move)),
+            Some(piece @ (ChessPieces::King | ChessPieces::Pawn)) => return Err(MoveError::InvalidPromotionPiece(piece)),
+            _ => {},
+        }
+        if !self.is_move_geometry_valid(next_move) {
+            return Err(if next_move.is_castling() { MoveError::CastlingNotAllowed(next_move) } else { MoveError::IllegalMovement(next_move) });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{chess_move, play, square};
+    use super::*;
+
+    fn board_with_pieces(pieces: &[(ChessPieces, ChessColors, &str)], to_move: ChessColors) -> ChessBoardState {
+        let moved = CastlingStateData { rook_a_moved: true, rook_h_moved: true, king_moved: true };
+        let pieces: Vec<PositionedChessPiece> = pieces.iter().map(|&(piece, color, position)| PositionedChessPiece { piece, color, position: square(position) }).collect();
+        let mut board = ChessBoardState {
+            placement: PiecePlacement::from_pieces(&pieces),
+            move_counter: 0,
+            to_move,
+            white_castling_state: moved,
+            black_castling_state: moved,
+            move_history: vec![],
+            undo_history: vec![],
+            halfmove_clock: 0,
+            en_passant_target: None,
+            initial_fen: None,
+            zobrist_keys: default_zobrist_keys(),
+            zobrist_key: 0,
+        };
+        board.zobrist_key = board.compute_zobrist_key();
+        board
+    }
+
+    #[test]
+    fn test_fen() {
+        let mut board = ChessBoardState::new();
+        let fen1 = board.to_fen();
+        assert_eq!(fen1, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        board.perform_move(ChessMove {
+            from: ChessBoardPosition { row: 1, column: 4 },
+            to: ChessBoardPosition { row: 3, column: 4 },
+            promotion: None,
+            piece: ChessPieces::Pawn,
+            color: ChessColors::White,
+        }).unwrap();
+        let fen2 = board.to_fen();
+        assert_eq!(fen2, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+
+    #[test]
+    fn test_piece_geometry() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let board = board_with_pieces(&[(King, White, "e1"), (King, Black, "e8"), (Rook, White, "a1"), (Knight, White, "d4"), (Bishop, White, "c1"), (Queen, White, "d1"), (Pawn, Black, "a5")], White);
+        assert!(board.is_move_valid(chess_move(Rook, White, "a1", "a5")));
+        assert!(!board.is_move_valid(chess_move(Rook, White, "a1", "b2")));
+        assert!(!board.is_move_valid(chess_move(Rook, White, "a1", "a6")));
+        assert!(board.is_move_valid(chess_move(Knight, White, "d4", "e6")));
+        assert!(!board.is_move_valid(chess_move(Knight, White, "d4", "d6")));
+        assert!(board.is_move_valid(chess_move(Bishop, White, "c1", "h6")));
+        assert!(!board.is_move_valid(chess_move(Bishop, White, "c1", "c2")));
+        assert!(board.is_move_valid(chess_move(Queen, White, "d1", "d3")));
+        assert!(!board.is_move_valid(chess_move(Queen, White, "d1", "d5")));
+        assert!(!board.is_move_valid(chess_move(Queen, White, "d1", "e1")));
+        assert!(board.is_move_valid(chess_move(King, White, "e1", "f2")));
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "e3")));
+    }
+
+    #[test]
+    fn test_pawn_moves() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let pieces = [(King, White, "e1"), (King, Black, "e8"), (Pawn, White, "e2"), (Pawn, White, "d2"), (Bishop, Black, "d3"), (Pawn, Black, "f3"), (Pawn, White, "c3")];
+        let board = board_with_pieces(&pieces, White);
+        assert!(board.is_move_valid(chess_move(Pawn, White, "e2", "e3")));
+        assert!(board.is_move_valid(chess_move(Pawn, White, "e2", "e4")));
+        assert!(board.is_move_valid(chess_move(Pawn, White, "e2", "f3")));
+        assert!(board.is_move_valid(chess_move(Pawn, White, "e2", "d3")));
+        assert!(!board.is_move_valid(chess_move(Pawn, White, "d2", "d3")));
+        assert!(!board.is_move_valid(chess_move(Pawn, White, "d2", "d4")));
+        assert!(!board.is_move_valid(chess_move(Pawn, White, "c3", "c5")));
+        assert!(!board.is_move_valid(chess_move(Pawn, White, "c3", "b4")));
+        assert!(!board.is_move_valid(chess_move(Pawn, White, "e2", "e1")));
+        assert_eq!(board.validate_move(chess_move(Pawn, Black, "f3", "f2")), Err(MoveError::WrongSideToMove(Black)));
+        let board = board_with_pieces(&pieces, Black);
+        assert!(board.is_move_valid(chess_move(Pawn, Black, "f3", "f2")));
+        assert!(!board.is_move_valid(chess_move(Pawn, Black, "f3", "f4")));
+    }
+
+    #[test]
+    fn test_promotion_and_side_to_move() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = ChessBoardState::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = chess_move(Pawn, White, "b7", "b8");
+        assert_eq!(board.perform_move(promotion), Err(MoveError::MissingPromotion(promotion)));
+        assert_eq!(board.perform_move(ChessMove { promotion: Some(King), ..promotion }), Err(MoveError::InvalidPromotionPiece(King)));
+        assert_eq!(board.perform_move(ChessMove { promotion: Some(Pawn), ..promotion }), Err(MoveError::InvalidPromotionPiece(Pawn)));
+        let king_move = ChessMove { promotion: Some(Queen), ..chess_move(King, White, "e1", "e2") };
+        assert_eq!(board.perform_move(king_move), Err(MoveError::UnexpectedPromotion(king_move)));
+        assert!(board.perform_move(ChessMove { promotion: Some(Rook), ..promotion }).is_ok());
+        assert_eq!(board.perform_move(chess_move(King, White, "e1", "e2")), Err(MoveError::WrongSideToMove(White)));
+        assert_eq!(board.to_fen(), "1R2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn test_moves_into_check() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let board = board_with_pieces(&[(King, White, "e1"), (King, Black, "e8"), (Knight, White, "e2"), (Rook, Black, "e7"), (Knight, Black, "b3"), (Rook, White, "h1")], White);
+        assert!(!board.is_move_valid(chess_move(Knight, White, "e2", "c3")));
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "d2")));
+        assert!(board.is_move_valid(chess_move(King, White, "e1", "f2")));
+        assert!(board.is_move_valid(chess_move(Rook, White, "h1", "h7")));
+        let board = board_with_pieces(&[(King, White, "h1"), (King, Black, "e8"), (Rook, White, "a8"), (Bishop, Black, "d8")], Black);
+        assert!(!board.is_move_valid(chess_move(Bishop, Black, "d8", "e7")));
+        assert!(board.is_move_valid(chess_move(King, Black, "e8", "e7")));
+    }
+
+    #[test]
+    fn test_castling() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let unmoved = CastlingStateData { rook_a_moved: false, rook_h_moved: false, king_moved: false };
+        let mut board = board_with_pieces(&[(King, White, "e1"), (Rook, White, "a1"), (Rook, White, "h1"), (King, Black, "e8"), (Rook, Black, "a8"), (Rook, Black, "h8"), (Bishop, Black, "a6")], White);
+        board.white_castling_state = unmoved;
+        board.black_castling_state = unmoved;
+        // The bishop on a6 attacks f1, so white can only castle queenside.
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "g1")));
+        assert!(board.perform_move(chess_move(King, White, "e1", "c1")).is_ok());
+        assert_eq!(board.get_piece_at(square("d1")).map(|piece| piece.piece), Some(Rook));
+        assert!(board.get_piece_at(square("a1")).is_none());
+        assert!(board.castling_state(White).king_moved);
+        assert!(board.move_history().last().unwrap().is_castling());
+        assert!(board.perform_move(chess_move(King, Black, "e8", "g8")).is_ok());
+        assert_eq!(board.get_piece_at(square("f8")).map(|piece| piece.piece), Some(Rook));
+        assert_eq!(board.get_piece_at(square("g8")).map(|piece| piece.piece), Some(King));
+    }
+
+    #[test]
+    fn test_castling_conditions() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let unmoved = CastlingStateData { rook_a_moved: false, rook_h_moved: false, king_moved: false };
+        let mut board = board_with_pieces(&[(King, White, "e1"), (Rook, White, "a1"), (Rook, White, "h1"), (Knight, White, "b1"), (King, Black, "e8"), (Rook, Black, "e5")], White);
+        board.white_castling_state = unmoved;
+        // In check, castling is not allowed in either direction.
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "g1")));
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "c1")));
+        let mut board = board_with_pieces(&[(King, White, "e1"), (Rook, White, "a1"), (Rook, White, "h1"), (Knight, White, "b1"), (King, Black, "e8"), (Rook, Black, "g5")], White);
+        board.white_castling_state = unmoved;
+        // The king would end up in check on g1 and the knight blocks the queenside.
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "g1")));
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "c1")));
+        let mut board = board_with_pieces(&[(King, White, "e1"), (Rook, White, "a1"), (Rook, White, "h1"), (King, Black, "e8"), (Rook, Black, "b5")], White);
+        board.white_castling_state = unmoved;
+        // Only the rook passes the attacked square b1, which is allowed.
+        assert!(board.is_move_valid(chess_move(King, White, "e1", "c1")));
+        board.white_castling_state.rook_a_moved = true;
+        assert!(!board.is_move_valid(chess_move(King, White, "e1", "c1")));
+        assert!(board.is_move_valid(chess_move(King, White, "e1", "g1")));
+    }
+
+    #[test]
+    fn test_castling_rights_after_capture_on_corner() {
+        let cases = [
+            ("r3k2r/8/8/8/8/2B5/8/R3K2R w KQkq - 0 1", "Bxh8", "KQq"),
+            ("r3k2r/8/8/8/8/5B2/8/R3K2R w KQkq - 0 1", "Bxa8", "KQk"),
+            ("r3k2r/8/2b5/8/8/8/8/R3K2R b KQkq - 0 1", "Bxh1", "Qkq"),
+            ("r3k2r/8/5b2/8/8/8/8/R3K2R b KQkq - 0 1", "Bxa1", "Kkq"),
+            // The rook leaves its corner and captures on the other one.
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "Rxa8+", "Kk"),
+        ];
+        for (fen, san, castling) in cases {
+            let mut board = ChessBoardState::from_fen(fen).unwrap();
+            let next_move = board.parse_san(san).unwrap();
+            assert!(board.perform_move(next_move).is_ok());
+            assert_eq!(board.to_fen().split(' ').nth(2), Some(castling), "after {}", san);
+            assert_eq!(board.zobrist_key(), board.compute_zobrist_key());
+        }
+        // With the rook on h8 gone, black cannot castle kingside even after a new rook arrives there.
+        let mut board = ChessBoardState::from_fen("r3k2r/7r/8/8/8/2B5/8/R3K2R w KQkq - 0 1").unwrap();
+        play(&mut board, &["Bxh8", "Rxh8", "Rb1"]);
+        assert!(!board.is_move_valid(chess_move(ChessPieces::King, ChessColors::Black, "e8", "g8")));
+        assert!(board.is_move_valid(chess_move(ChessPieces::King, ChessColors::Black, "e8", "c8")));
+    }
+
+    #[test]
+    fn test_en_passant() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = ChessBoardState::new();
+        assert!(board.perform_move(chess_move(Pawn, White, "e2", "e4")).is_ok());
+        assert!(board.perform_move(chess_move(Pawn, Black, "a7", "a6")).is_ok());
+        assert!(board.perform_move(chess_move(Pawn, White, "e4", "e5")).is_ok());
+        assert!(board.perform_move(chess_move(Pawn, Black, "d7", "d5")).is_ok());
+        assert!(board.is_en_passant(chess_move(Pawn, White, "e5", "d6")));
+        let mut later = board.clone();
+        assert!(board.perform_move(chess_move(Pawn, White, "e5", "d6")).is_ok());
+        assert!(board.get_piece_at(square("d5")).is_none());
+        assert_eq!(board.get_piece_at(square("d6")).map(|piece| piece.color), Some(White));
+        assert_eq!(board.halfmove_clock(), 0);
+        assert!(later.perform_move(chess_move(Pawn, White, "a2", "a3")).is_ok());
+        assert!(later.perform_move(chess_move(Pawn, Black, "h7", "h6")).is_ok());
+        assert!(!later.is_move_valid(chess_move(Pawn, White, "e5", "d6")));
+    }
+
+    #[test]
+    fn test_en_passant_exposing_king() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = board_with_pieces(&[(King, White, "a5"), (Pawn, White, "b5"), (Pawn, Black, "c7"), (Rook, Black, "h5"), (King, Black, "e8")], Black);
+        assert!(board.perform_move(chess_move(Pawn, Black, "c7", "c5")).is_ok());
+        assert!(!board.is_move_valid(chess_move(Pawn, White, "b5", "c6")));
+        let mut board = board_with_pieces(&[(King, White, "a1"), (Pawn, White, "b5"), (Pawn, Black, "c7"), (Rook, Black, "h5"), (King, Black, "e8")], Black);
+        assert!(board.perform_move(chess_move(Pawn, Black, "c7", "c5")).is_ok());
+        assert!(board.perform_move(chess_move(Pawn, White, "b5", "c6")).is_ok());
+        assert!(board.get_piece_at(square("c5")).is_none());
+    }
+
+    #[test]
+    fn test_undo_move() {
+        let mut board = ChessBoardState::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 4 30").unwrap();
+        let mut fens = vec![board.to_fen()];
+        for uci in ["e5d6", "e8g8", "b7b8n", "a8b8", "e1c1", "f8f2", "d6d7", "b8b1"] {
+            let next_move = board.parse_uci_move(uci).unwrap();
+            assert!(board.perform_move(next_move).is_ok());
+            fens.push(board.to_fen());
+        }
+        assert_eq!(board.to_fen(), "6k1/3P4/8/8/8/8/5r2/1rKR3R w - - 1 34");
+        fens.pop();
+        while let Some(fen) = fens.pop() {
+            assert!(board.undo_move().is_some());
+            assert_eq!(board.to_fen(), fen);
+        }
+        assert!(board.undo_move().is_none());
+        assert!(board.move_history().is_empty());
+        assert_eq!(board.legal_moves().len(), ChessBoardState::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 4 30").unwrap().legal_moves().len());
+    }
+
+    #[test]
+    fn test_move_errors() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = ChessBoardState::new();
+        assert_eq!(board.perform_move(chess_move(Pawn, White, "e3", "e4")), Err(MoveError::NoPieceOnSquare(square("e3"))));
+        assert_eq!(board.perform_move(chess_move(Knight, White, "e2", "e4")), Err(MoveError::PieceMismatch { position: square("e2"), piece: Pawn, color: White }));
+        assert_eq!(board.perform_move(chess_move(Pawn, White, "e2", "e5")), Err(MoveError::IllegalMovement(chess_move(Pawn, White, "e2", "e5"))));
+        assert_eq!(board.perform_move(chess_move(King, White, "e1", "g1")), Err(MoveError::CastlingNotAllowed(chess_move(King, White, "e1", "g1"))));
+        let off_board = ChessMove { to: ChessBoardPosition { row: 8, column: 4 }, ..chess_move(Pawn, White, "e2", "e4") };
+        assert_eq!(board.perform_move(off_board), Err(MoveError::SquareOffBoard(ChessBoardPosition { row: 8, column: 4 })));
+        let mut board = board_with_pieces(&[(King, White, "e1"), (Bishop, White, "e2"), (Rook, Black, "e8"), (King, Black, "a8")], White);
+        assert_eq!(board.perform_move(chess_move(Bishop, White, "e2", "d3")), Err(MoveError::LeavesKingInCheck(chess_move(Bishop, White, "e2", "d3"))));
+        assert_eq!(board.to_fen(), "k3r3/8/8/8/8/8/4B3/4K3 w - - 0 1");
+        assert!(board.move_history().is_empty());
+        assert_eq!(board.zobrist_key(), board.compute_zobrist_key());
+    }
+
+    #[test]
+    fn test_move_outcomes() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = ChessBoardState::from_fen("4k2r/1P6/8/3pP3/8/8/8/4K2R w Kk d6 0 1").unwrap();
+        let outcome = board.perform_move(chess_move(Pawn, White, "e5", "d6")).unwrap();
+        assert!(outcome.is_en_passant);
+        assert_eq!(outcome.captured, Some(PositionedChessPiece { piece: Pawn, color: Black, position: square("d5") }));
+        assert!(!outcome.is_check);
+        let outcome = board.perform_move(chess_move(King, Black, "e8", "g8")).unwrap();
+        assert!(outcome.is_castling);
+        assert_eq!(outcome.captured, None);
+        let outcome = board.perform_move(ChessMove { promotion: Some(Knight), ..chess_move(Pawn, White, "b7", "b8") }).unwrap();
+        assert_eq!(outcome.promotion, Some(Knight));
+        assert!(!outcome.is_castling && !outcome.is_en_passant);
+        let mut board = ChessBoardState::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let outcome = board.perform_move(chess_move(Rook, White, "a1", "a8")).unwrap();
+        assert!(outcome.is_check && outcome.is_checkmate);
+        assert_eq!(outcome.promotion, None);
+    }
+}
@@ -0,0 +1,439 @@
+use crate::board_state::bitboard::PiecePlacement;
+use crate::board_state::zobrist::default_zobrist_keys;
+use crate::{CastlingStateData, ChessBoardPosition, ChessBoardState, ChessColors, ChessMove, ChessPieces, FenEnPassantMode, FenError, PositionedChessPiece};
+
+/// The standard starting position, which boards created from it do not record as their initial FEN.
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn piece_from_fen_character(character: char) -> Option<(ChessPieces, ChessColors)> {
+    let piece = match character.to_ascii_lowercase() {
+        'k' => ChessPieces::King,
+        'q' => ChessPieces::Queen,
+        'r' => ChessPieces::Rook,
+        'b' => ChessPieces::Bishop,
+        'n' => ChessPieces::Knight,
+        'p' => ChessPieces::Pawn,
+        _ => return None,
+    };
+    let color = if character.is_ascii_uppercase() { ChessColors::White } else { ChessColors::Black };
+    Some((piece, color))
+}
+
+fn piece_to_fen_character(piece: &PositionedChessPiece) -> char {
+    let character = match piece.piece {
+        ChessPieces::King => 'k',
+        ChessPieces::Queen => 'q',
+        ChessPieces::Rook => 'r',
+        ChessPieces::Bishop => 'b',
+        ChessPieces::Knight => 'n',
+        ChessPieces::Pawn => 'p',
+    };
+    match piece.color {
+        ChessColors::White => character.to_ascii_uppercase(),
+        ChessColors::Black => character,
+    }
+}
+
+fn parse_piece_placement(field: &str) -> Result<Vec<PositionedChessPiece>, FenError> {
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongNumberOfRanks(ranks.len()));
+    }
+    let mut pieces = vec![];
+    for (index, rank) in ranks.iter().enumerate() {
+        let row = 7 - index as u8;
+        let mut column: u8 = 0;
+        let mut previous_was_digit = false;
+        for character in rank.chars() {
+            if let Some(empty_squares) = character.to_digit(10) {
+                if !(1..=8).contains(&empty_squares) {
+                    return Err(FenError::InvalidPieceCharacter(character));
+                }
+                if previous_was_digit {
+                    return Err(FenError::ConsecutiveDigits(row + 1));
+                }
+                previous_was_digit = true;
+                column += empty_squares as u8;
+            } else {
+                previous_was_digit = false;
+                let (piece, color) = piece_from_fen_character(character).ok_or(FenError::InvalidPieceCharacter(character))?;
+                if column < 8 {
+                    pieces.push(PositionedChessPiece { piece, color, position: ChessBoardPosition { row, column } });
+                }
+                column += 1;
+            }
+            if column > 8 {
+                return Err(FenError::InvalidRankLength(row + 1));
+            }
+        }
+        if column != 8 {
+            return Err(FenError::InvalidRankLength(row + 1));
+        }
+    }
+    Ok(pieces)
+}
+
+fn validate_piece_counts(pieces: &[PositionedChessPiece]) -> Result<(), FenError> {
+    for color in [ChessColors::White, ChessColors::Black] {
+        let count = |piece_type: ChessPieces| pieces.iter().filter(|piece| piece.color == color && piece.piece == piece_type).count();
+        match count(ChessPieces::King) {
+            0 => return Err(FenError::MissingKing(color)),
+            1 => {},
+            _ => return Err(FenError::TooManyKings(color)),
+        }
+        if count(ChessPieces::Pawn) > 8 {
+            return Err(FenError::TooManyPawns(color));
+        }
+        if pieces.iter().filter(|piece| piece.color == color).count() > 16 {
+            return Err(FenError::TooManyPieces(color));
+        }
+    }
+    if let Some(pawn) = pieces.iter().find(|piece| piece.piece == ChessPieces::Pawn && (piece.position.row == 0 || piece.position.row == 7)) {
+        return Err(FenError::PawnOnBackRank(pawn.position));
+    }
+    Ok(())
+}
+
+/// Translates the castling field into the castling state of both sides.
+fn parse_castling_rights(field: &str) -> Result<(CastlingStateData, CastlingStateData), FenError> {
+    let mut rights = [false; 4];
+    if field != "-" {
+        for character in field.chars() {
+            let index = match character {
+                'K' => 0,
+                'Q' => 1,
+                'k' => 2,
+                'q' => 3,
+                _ => return Err(FenError::InvalidCastlingRights(field.to_string())),
+            };
+            if rights[index] {
+                return Err(FenError::InvalidCastlingRights(field.to_string()));
+            }
+            rights[index] = true;
+        }
+    }
+    let state = |kingside: bool, queenside: bool| CastlingStateData {
+        rook_a_moved: !queenside,
+        rook_h_moved: !kingside,
+        king_moved: !kingside && !queenside,
+    };
+    Ok((state(rights[0], rights[1]), state(rights[2], rights[3])))
+}
+
+impl ChessBoardState {
+    /// Creates a board state from FEN, rejecting malformed and impossible positions.
+    pub fn from_fen(fen: &str) -> Result<ChessBoardState, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(FenError::WrongNumberOfFields(fields.len()));
+        }
+        let pieces = parse_piece_placement(fields[0])?;
+        validate_piece_counts(&pieces)?;
+        let to_move = match fields[1] {
+            "w" => ChessColors::White,
+            "b" => ChessColors::Black,
+            _ => return Err(FenError::InvalidSideToMove(fields[1].to_string())),
+        };
+        let (white_castling_state, black_castling_state) = parse_castling_rights(fields[2])?;
+        let en_passant_target = match fields[3] {
+            "-" => None,
+            square => Some(ChessBoardPosition::from_algebraic(square).ok_or_else(|| FenError::InvalidEnPassantSquare(square.to_string()))?),
+        };
+        let halfmove_clock: i32 = fields[4].parse().ok().filter(|clock| *clock >= 0).ok_or_else(|| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+        let fullmove_number: i32 = fields[5].parse().ok().filter(|number| *number >= 1).ok_or_else(|| FenError::InvalidFullmoveNumber(fields[5].to_string()))?;
+        let move_counter = (fullmove_number - 1)
+            .checked_mul(2)
+            .and_then(|counter| counter.checked_add(if to_move == ChessColors::Black { 1 } else { 0 }))
+            .ok_or_else(|| FenError::InvalidFullmoveNumber(fields[5].to_string()))?;
+        let mut board = ChessBoardState {
+            placement: PiecePlacement::from_pieces(&pieces),
+            move_counter,
+            to_move,
+            white_castling_state,
+            black_castling_state,
+            move_history: vec![],
+            undo_history: vec![],
+            halfmove_clock,
+            en_passant_target,
+            initial_fen: Some(fields.join(" ")).filter(|fen| fen != START_FEN),
+            zobrist_keys: default_zobrist_keys(),
+            zobrist_key: 0,
+        };
+        board.zobrist_key = board.compute_zobrist_key();
+        board.validate_castling_rights()?;
+        board.validate_en_passant_target()?;
+        if board.is_in_check(to_move.opponent()) {
+            return Err(FenError::SideNotToMoveInCheck(to_move.opponent()));
+        }
+        Ok(board)
+    }
+
+    /// Exports the current position in Forsyth-Edwards Notation.
+    pub fn to_fen(&self) -> String {
+        self.to_fen_with_en_passant_mode(FenEnPassantMode::Always)
+    }
+
+    /// Exports the current position in Forsyth-Edwards Notation, writing the en passant square according to the given mode.
+    pub fn to_fen_with_en_passant_mode(&self, mode: FenEnPassantMode) -> String {
+        let mut fen = String::new();
+        for row in (0..8).rev() {
+            let mut empty_counter = 0;
+            for column in 0..8 {
+                match self.get_piece_at(ChessBoardPosition { row, column }) {
+                    Some(piece) => {
+                        if empty_counter > 0 {
+                            fen.push_str(&empty_counter.to_string());
+                            empty_counter = 0;
+                        }
+                        fen.push(piece_to_fen_character(&piece));
+                    },
+                    None => empty_counter += 1,
+                }
+            }
+            if empty_counter > 0 {
+                fen.push_str(&empty_counter.to_string());
+            }
+            if row > 0 {
+                fen.push('/');
+            }
+        }
+        fen.push(' ');
+        fen.push(match self.to_move {
+            ChessColors::White => 'w',
+            ChessColors::Black => 'b',
+        });
+        fen.push(' ');
+        fen.push_str(&self.get_castling_field());
+        fen.push(' ');
+        match self.en_passant_target {
+            Some(target) if mode == FenEnPassantMode::Always || self.is_en_passant_capture_available() => fen.push_str(&target.to_string()),
+            _ => fen.push('-'),
+        }
+        fen.push(' ');
+        fen.push_str(&self.halfmove_clock.to_string());
+        fen.push(' ');
+        fen.push_str(&self.fullmove_number().to_string());
+        fen
+    }
+
+    /// The number of the current full move, starting at 1.
+    pub fn fullmove_number(&self) -> i32 {
+        self.move_counter / 2 + 1
+    }
+
+    fn get_castling_field(&self) -> String {
+        let mut field = String::new();
+        for (castling_state, kingside, queenside) in [(self.white_castling_state, 'K', 'Q'), (self.black_castling_state, 'k', 'q')] {
+            if castling_state.king_moved {
+                continue;
+            }
+            if !castling_state.rook_h_moved {
+                field.push(kingside);
+            }
+            if !castling_state.rook_a_moved {
+                field.push(queenside);
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    /// Returns true if a pawn of the side to move can legally capture en passant.
+    pub(crate) fn is_en_passant_capture_available(&self) -> bool {
+        let target = match self.en_passant_target {
+            Some(target) => target,
+            None => return false,
+        };
+        let pawn_row = match self.to_move {
+            ChessColors::White => target.row as i32 - 1,
+            ChessColors::Black => target.row as i32 + 1,
+        };
+        [target.column as i32 - 1, target.column as i32 + 1].iter().filter(|column| (0..8).contains(*column) && (0..8).contains(&pawn_row)).any(|&column| {
+            self.is_move_valid(ChessMove {
+                from: ChessBoardPosition { row: pawn_row as u8, column: column as u8 },
+                to: target,
+                promotion: None,
+                piece: ChessPieces::Pawn,
+                color: self.to_move,
+            })
+        })
+    }
+
+    fn has_piece_on(&self, piece: ChessPieces, color: ChessColors, row: u8, column: u8) -> bool {
+        match self.get_piece_at(ChessBoardPosition { row, column }) {
+            Some(found) => found.piece == piece && found.color == color,
+            None => false,
+        }
+    }
+
+    fn validate_castling_rights(&self) -> Result<(), FenError> {
+        for (color, row, kingside, queenside) in [(ChessColors::White, 0, 'K', 'Q'), (ChessColors::Black, 7, 'k', 'q')] {
+            let castling_state = self.castling_state(color);
+            if castling_state.king_moved {
+                continue;
+            }
+            let king_home = self.has_piece_on(ChessPieces::King, color, row, 4);
+            let kingside_pieces_home = king_home && self.has_piece_on(ChessPieces::Rook, color, row, 7);
+            let queenside_pieces_home = king_home && self.has_piece_on(ChessPieces::Rook, color, row, 0);
+            if !castling_state.rook_h_moved && !kingside_pieces_home {
+                return Err(FenError::CastlingRightsInconsistent(kingside));
+            }
+            if !castling_state.rook_a_moved && !queenside_pieces_home {
+                return Err(FenError::CastlingRightsInconsistent(queenside));
+            }
+        }
+        Ok(())
+    }
+
+    /// The en passant square has to be behind a pawn of the side that just moved, with both squares it crossed empty.
+    fn validate_en_passant_target(&self) -> Result<(), FenError> {
+        let target = match self.en_passant_target {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        let (target_row, pawn_row, start_row) = match self.to_move {
+            ChessColors::White => (5, 4, 6),
+            ChessColors::Black => (2, 3, 1),
+        };
+        let start = ChessBoardPosition { row: start_row, column: target.column };
+        if target.row != target_row
+            || !self.has_piece_on(ChessPieces::Pawn, self.to_move.opponent(), pawn_row, target.column)
+            || self.get_piece_at(target).is_some()
+            || self.get_piece_at(start).is_some() {
+            return Err(FenError::ImplausibleEnPassantSquare(target));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_position() {
+        let board = ChessBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let start = ChessBoardState::new();
+        assert_eq!(board.pieces().count(), 32);
+        for piece in start.pieces() {
+            assert_eq!(board.get_piece_at(piece.position), Some(piece));
+        }
+        assert_eq!(board.to_move(), ChessColors::White);
+        assert_eq!(board.castling_state(ChessColors::White), start.castling_state(ChessColors::White));
+        assert_eq!(board.castling_state(ChessColors::Black), start.castling_state(ChessColors::Black));
+        assert_eq!(board.legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let board = ChessBoardState::from_fen("r3k2r/8/8/3pP3/8/8/8/4K2R w Kq d6 3 20").unwrap();
+        assert_eq!(board.to_move(), ChessColors::White);
+        assert_eq!(board.en_passant_target(), ChessBoardPosition::from_algebraic("d6"));
+        assert_eq!(board.halfmove_clock(), 3);
+        assert_eq!(board.move_counter, 38);
+        assert_eq!(board.castling_state(ChessColors::White), CastlingStateData { rook_a_moved: true, rook_h_moved: false, king_moved: false });
+        assert_eq!(board.castling_state(ChessColors::Black), CastlingStateData { rook_a_moved: false, rook_h_moved: true, king_moved: false });
+        let board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert!(board.castling_state(ChessColors::White).king_moved);
+        assert!(board.castling_state(ChessColors::Black).king_moved);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(ChessBoardState::from_fen("8/8/8/8/8/8/8/8 w - -").err(), Some(FenError::WrongNumberOfFields(4)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/4K3 w - - 0 1").err(), Some(FenError::WrongNumberOfRanks(7)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1").err(), Some(FenError::InvalidRankLength(1)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/7/4K3 w - - 0 1").err(), Some(FenError::InvalidRankLength(2)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4X3 w - - 0 1").err(), Some(FenError::InvalidPieceCharacter('X')));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/44/8/8/4K3 w - - 0 1").err(), Some(FenError::ConsecutiveDigits(4)));
+        assert_eq!(ChessBoardState::from_fen("4k3/1111pppp/8/8/8/8/8/4K3 w - - 0 1").err(), Some(FenError::ConsecutiveDigits(7)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/3K13 w - - 0 1").err(), Some(FenError::ConsecutiveDigits(1)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1").err(), Some(FenError::InvalidSideToMove("x".to_string())));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w KK - 0 1").err(), Some(FenError::InvalidCastlingRights("KK".to_string())));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - e9 0 1").err(), Some(FenError::InvalidEnPassantSquare("e9".to_string())));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - -1 1").err(), Some(FenError::InvalidHalfmoveClock("-1".to_string())));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 0").err(), Some(FenError::InvalidFullmoveNumber("0".to_string())));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 2147483647").err(), Some(FenError::InvalidFullmoveNumber("2147483647".to_string())));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 2147483648").err(), Some(FenError::InvalidFullmoveNumber("2147483648".to_string())));
+    }
+
+    #[test]
+    fn test_large_counters() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 b - - 2147483647 1073741824";
+        let mut board = ChessBoardState::from_fen(fen).unwrap();
+        assert_eq!(board.move_counter, i32::MAX);
+        // The game is drawn, but make and undo still have to cope with the counters, e.g. in perft.
+        assert_eq!(board.perft(2), 25);
+        for san in ["Kd7", "Kd2"] {
+            let next_move = board.parse_san(san).unwrap();
+            board.make_move(next_move);
+        }
+        assert_eq!(board.halfmove_clock(), i32::MAX);
+        assert!(board.undo_move().is_some());
+        assert!(board.undo_move().is_some());
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_implausible_positions() {
+        assert_eq!(ChessBoardState::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").err(), Some(FenError::MissingKing(ChessColors::Black)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").err(), Some(FenError::TooManyKings(ChessColors::White)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/PPPPPPPP/P3K3 w - - 0 1").err(), Some(FenError::TooManyPawns(ChessColors::White)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/QQQQQQQQ/QQQQQQQQ/Q3K3 w - - 0 1").err(), Some(FenError::TooManyPieces(ChessColors::White)));
+        assert_eq!(ChessBoardState::from_fen("4k2P/8/8/8/8/8/8/4K3 w - - 0 1").err(), Some(FenError::PawnOnBackRank(ChessBoardPosition { row: 7, column: 7 })));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K2R b - - 0 1").ok().map(|board| board.to_move()), Some(ChessColors::Black));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4R2K w - - 0 1").err(), Some(FenError::SideNotToMoveInCheck(ChessColors::Black)));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").err(), Some(FenError::CastlingRightsInconsistent('K')));
+        assert_eq!(ChessBoardState::from_fen("4k2r/8/8/8/8/8/8/4K3 w q - 0 1").err(), Some(FenError::CastlingRightsInconsistent('q')));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - e6 0 1").err(), Some(FenError::ImplausibleEnPassantSquare(ChessBoardPosition { row: 5, column: 4 })));
+        assert_eq!(ChessBoardState::from_fen("4k3/8/8/3p4/8/8/8/4K3 b - d6 0 1").err(), Some(FenError::ImplausibleEnPassantSquare(ChessBoardPosition { row: 5, column: 3 })));
+        assert!(ChessBoardState::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1").is_ok());
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 12 40",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        ] {
+            assert_eq!(ChessBoardState::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_fen_after_moves() {
+        let mut board = ChessBoardState::new();
+        let moves = [("g1", "f3", ChessPieces::Knight), ("g8", "f6", ChessPieces::Knight), ("h1", "g1", ChessPieces::Rook), ("b8", "c6", ChessPieces::Knight), ("e2", "e4", ChessPieces::Pawn)];
+        for (index, (from, to, piece)) in moves.iter().enumerate() {
+            let color = if index % 2 == 0 { ChessColors::White } else { ChessColors::Black };
+            assert!(board.perform_move(ChessMove {
+                from: ChessBoardPosition::from_algebraic(from).unwrap(),
+                to: ChessBoardPosition::from_algebraic(to).unwrap(),
+                promotion: None,
+                piece: *piece,
+                color,
+            }).is_ok());
+        }
+        let fen = board.to_fen();
+        assert_eq!(fen, "r1bqkb1r/pppppppp/2n2n2/8/4P3/5N2/PPPP1PPP/RNBQKBR1 b Qkq e3 0 3");
+        assert_eq!(board.to_fen_with_en_passant_mode(FenEnPassantMode::OnlyWhenCapturable), "r1bqkb1r/pppppppp/2n2n2/8/4P3/5N2/PPPP1PPP/RNBQKBR1 b Qkq - 0 3");
+        assert_eq!(ChessBoardState::from_fen(&fen).unwrap().to_fen(), fen);
+    }
+
+    #[test]
+    fn test_en_passant_modes() {
+        let board = ChessBoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(board.to_fen_with_en_passant_mode(FenEnPassantMode::OnlyWhenCapturable), "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        // The capture would expose the white king to the rook on the fifth rank.
+        let board = ChessBoardState::from_fen("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1").unwrap();
+        assert_eq!(board.to_fen_with_en_passant_mode(FenEnPassantMode::OnlyWhenCapturable), "8/8/8/K2pP2r/8/8/8/4k3 w - - 0 1");
+        assert_eq!(board.to_fen(), "8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1");
+        let board = ChessBoardState::from_fen("4k3/8/8/8/3p4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.to_fen_with_en_passant_mode(FenEnPassantMode::OnlyWhenCapturable), "4k3/8/8/8/3p4/8/8/4K3 w - - 0 1");
+    }
+}
@@ -0,0 +1,235 @@
+use crate::board_state::bitboard::Squares;
+use crate::{ChessBoardState, ChessColors, ChessPieces, DrawReason, GameStatus};
+
+impl ChessBoardState {
+    /// Determines whether the game is still running or how it ended, based on the current position and the legal moves of the side to move.
+    pub fn game_status(&self) -> GameStatus {
+        if !self.copy_position().has_legal_move() {
+            if self.is_in_check(self.to_move()) {
+                return GameStatus::Checkmate { winner: self.to_move().opponent() };
+            }
+            return GameStatus::Draw(DrawReason::Stalemate);
+        }
+        match self.automatic_draw() {
+            Some(reason) => GameStatus::Draw(reason),
+            None => GameStatus::Ongoing,
+        }
+    }
+
+    /// The draw that ends the game regardless of the players, if any; perform_move rejects moves after it.
+    pub(crate) fn automatic_draw(&self) -> Option<DrawReason> {
+        if self.is_insufficient_material() {
+            return Some(DrawReason::InsufficientMaterial);
+        }
+        if self.halfmove_clock() >= 150 {
+            return Some(DrawReason::SeventyFiveMoveRule);
+        }
+        if self.repetition_count() >= 5 {
+            return Some(DrawReason::FivefoldRepetition);
+        }
+        None
+    }
+
+    /// Returns true if the side to move is in check and has no legal move.
+    pub fn is_checkmate(&self) -> bool {
+        matches!(self.game_status(), GameStatus::Checkmate { .. })
+    }
+
+    /// Returns true if the side to move is not in check but has no legal move.
+    pub fn is_stalemate(&self) -> bool {
+        self.game_status() == GameStatus::Draw(DrawReason::Stalemate)
+    }
+
+    /// How often the current position has occurred in the game, including now, compared by Zobrist key and so with the Polyglot rule for en passant rights.
+    pub fn repetition_count(&self) -> usize {
+        // Positions before a pawn move or a capture cannot be repeated, so only the moves since then are looked at.
+        let reversible_moves = self.undo_history.len().min(self.halfmove_clock().max(0) as usize);
+        1 + self.undo_history.iter().rev().take(reversible_moves).filter(|undo_data| undo_data.zobrist_key == self.zobrist_key()).count()
+    }
+
+    /// Returns true if the current position occurred at least three times, which allows the player to move to claim a draw.
+    pub fn can_claim_threefold(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// Returns true if the last 50 moves by each side were played without a pawn move or a capture, which allows either player to claim a draw.
+    pub fn can_claim_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock() >= 100
+    }
+
+    /// Returns true if neither side can checkmate by any sequence of legal moves, like in K vs K.
+    pub fn is_insufficient_material(&self) -> bool {
+        self.has_insufficient_material(ChessColors::White) && self.has_insufficient_material(ChessColors::Black)
+    }
+
+    /// Returns true if the given side can never checkmate, which decides games lost on time.
+    pub fn has_insufficient_material(&self, color: ChessColors) -> bool {
+        let own_pieces: Vec<ChessPieces> = self.pieces().filter(|piece| piece.color == color).map(|piece| piece.piece).collect();
+        if own_pieces.iter().any(|&piece| matches!(piece, ChessPieces::Pawn | ChessPieces::Rook | ChessPieces::Queen)) {
+            return false;
+        }
+        if own_pieces.contains(&ChessPieces::Knight) {
+            // A lone knight can only mate a king that is boxed in by its own pieces, which a queen cannot do.
+            return own_pieces.len() <= 2 && self.pieces().all(|piece| piece.color == color || matches!(piece.piece, ChessPieces::King | ChessPieces::Queen));
+        }
+        if own_pieces.contains(&ChessPieces::Bishop) {
+            // Bishops that all stand on squares of one color can never attack the other squares, and without pawns or knights nothing can block the king on them.
+            let mut bishop_square_colors = self.pieces()
+                .filter(|piece| piece.piece == ChessPieces::Bishop)
+                .map(|piece| (piece.position.row + piece.position.column) % 2);
+            let first_square_color = bishop_square_colors.next();
+            return bishop_square_colors.all(|square_color| Some(square_color) == first_square_color)
+                && !self.pieces().any(|piece| matches!(piece.piece, ChessPieces::Pawn | ChessPieces::Knight));
+        }
+        true
+    }
+
+    /// Returns true as soon as a legal move of the side to move is found, looking ahead on this board.
+    pub(crate) fn has_legal_move(&mut self) -> bool {
+        let mut moves = vec![];
+        for position in Squares(self.placement.color(self.to_move)) {
+            if let Some(piece) = self.get_piece_at(position) {
+                self.append_legal_moves_for_piece(&piece, &mut moves);
+                if !moves.is_empty() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{chess_move, play};
+    use crate::{ChessColors, ChessPieces, MoveError};
+    use super::*;
+
+    #[test]
+    fn test_fools_mate() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = ChessBoardState::new();
+        assert_eq!(board.game_status(), GameStatus::Ongoing);
+        for next_move in [
+            chess_move(Pawn, White, "f2", "f3"),
+            chess_move(Pawn, Black, "e7", "e5"),
+            chess_move(Pawn, White, "g2", "g4"),
+            chess_move(Queen, Black, "d8", "h4"),
+        ] {
+            assert!(board.perform_move(next_move).is_ok());
+        }
+        assert_eq!(board.game_status(), GameStatus::Checkmate { winner: Black });
+        assert!(board.is_checkmate());
+        assert!(board.game_status().is_game_over());
+    }
+
+    #[test]
+    fn test_stalemate() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        // The fastest known stalemate, found by Sam Loyd.
+        let mut board = ChessBoardState::new();
+        for next_move in [
+            chess_move(Pawn, White, "e2", "e3"), chess_move(Pawn, Black, "a7", "a5"),
+            chess_move(Queen, White, "d1", "h5"), chess_move(Rook, Black, "a8", "a6"),
+            chess_move(Queen, White, "h5", "a5"), chess_move(Pawn, Black, "h7", "h5"),
+            chess_move(Pawn, White, "h2", "h4"), chess_move(Rook, Black, "a6", "h6"),
+            chess_move(Queen, White, "a5", "c7"), chess_move(Pawn, Black, "f7", "f6"),
+            chess_move(Queen, White, "c7", "d7"), chess_move(King, Black, "e8", "f7"),
+            chess_move(Queen, White, "d7", "b7"), chess_move(Queen, Black, "d8", "d3"),
+            chess_move(Queen, White, "b7", "b8"), chess_move(Queen, Black, "d3", "h7"),
+            chess_move(Queen, White, "b8", "c8"), chess_move(King, Black, "f7", "g6"),
+        ] {
+            assert!(board.perform_move(next_move).is_ok());
+        }
+        assert!(!board.is_stalemate());
+        assert!(board.perform_move(chess_move(Queen, White, "c8", "e6")).is_ok());
+        assert_eq!(board.game_status(), GameStatus::Draw(DrawReason::Stalemate));
+        assert!(!board.is_checkmate());
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut board = ChessBoardState::new();
+        play(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(board.repetition_count(), 2);
+        assert!(!board.can_claim_threefold());
+        play(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert!(board.can_claim_threefold());
+        assert_eq!(board.game_status(), GameStatus::Ongoing);
+        play(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"]);
+        assert_eq!(board.repetition_count(), 4);
+        play(&mut board, &["Ng8"]);
+        assert_eq!(board.repetition_count(), 5);
+        assert_eq!(board.game_status(), GameStatus::Draw(DrawReason::FivefoldRepetition));
+        let knight_move = board.parse_san("Nf3").unwrap();
+        assert_eq!(board.perform_move(knight_move), Err(MoveError::GameOver(DrawReason::FivefoldRepetition)));
+        assert!(!board.is_move_valid(knight_move));
+    }
+
+    #[test]
+    fn test_repetition_with_castling_rights_and_en_passant() {
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        play(&mut board, &["Kf1", "Kd7", "Ke1", "Ke8"]);
+        assert_eq!(board.repetition_count(), 1);
+        // The en passant capture possible right after the double push makes that position unique.
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        play(&mut board, &["e4", "Ke7", "Kf2", "Ke8", "Ke1"]);
+        assert_eq!(board.repetition_count(), 1);
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/2p5/8/4P3/4K3 w - - 0 1").unwrap();
+        play(&mut board, &["e4", "Ke7", "Kf2", "Ke8", "Ke1"]);
+        assert_eq!(board.repetition_count(), 2);
+    }
+
+    #[test]
+    fn test_move_rules() {
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert!(!board.can_claim_fifty_move_draw());
+        play(&mut board, &["Ra2"]);
+        assert!(board.can_claim_fifty_move_draw());
+        assert_eq!(board.game_status(), GameStatus::Ongoing);
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 80").unwrap();
+        play(&mut board, &["Ra2"]);
+        assert_eq!(board.game_status(), GameStatus::Draw(DrawReason::SeventyFiveMoveRule));
+        assert_eq!(board.perform_move(chess_move(ChessPieces::King, ChessColors::Black, "e8", "d8")), Err(MoveError::GameOver(DrawReason::SeventyFiveMoveRule)));
+        // A mate on the 150th ply still counts.
+        let mut board = ChessBoardState::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 149 80").unwrap();
+        play(&mut board, &["Ra8"]);
+        assert_eq!(board.game_status(), GameStatus::Checkmate { winner: ChessColors::White });
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for fen in ["4k3/8/8/8/8/8/8/4K3 w - - 0 1", "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1", "2b1k3/8/8/8/8/8/8/3BKB2 w - - 0 1"] {
+            let board = ChessBoardState::from_fen(fen).unwrap();
+            assert!(board.is_insufficient_material(), "{}", fen);
+            assert_eq!(board.game_status(), GameStatus::Draw(DrawReason::InsufficientMaterial));
+        }
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.perform_move(chess_move(ChessPieces::King, ChessColors::White, "e1", "e2")), Err(MoveError::GameOver(DrawReason::InsufficientMaterial)));
+        for fen in ["b3k3/8/8/8/8/8/8/2B1K3 w - - 0 1", "1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1", "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1", "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"] {
+            let board = ChessBoardState::from_fen(fen).unwrap();
+            assert!(!board.is_insufficient_material(), "{}", fen);
+            assert_eq!(board.game_status(), GameStatus::Ongoing);
+        }
+    }
+
+    #[test]
+    fn test_insufficient_material_per_side() {
+        use ChessColors::*;
+        // Black flagging with only a king left against a rook loses, White flagging draws.
+        let board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert!(!board.has_insufficient_material(White));
+        assert!(board.has_insufficient_material(Black));
+        // A knight can mate a king that is blocked by its own pawn, but no lone king can.
+        let board = ChessBoardState::from_fen("7k/7p/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        assert!(!board.has_insufficient_material(White));
+        assert!(!board.has_insufficient_material(Black));
+        let board = ChessBoardState::from_fen("4k3/8/8/8/8/8/8/1N1QK3 b - - 0 1").unwrap();
+        assert!(board.has_insufficient_material(Black));
+        let board = ChessBoardState::from_fen("3qk3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        assert!(board.has_insufficient_material(White));
+        assert!(!board.has_insufficient_material(Black));
+    }
+}
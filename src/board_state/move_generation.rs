@@ -0,0 +1,139 @@
+use crate::board_state::bitboard::{color_index, square_bit, square_index, Bitboard, Squares, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+use crate::{ChessBoardPosition, ChessBoardState, ChessColors, ChessMove, ChessPieces, PositionedChessPiece};
+
+const PROMOTION_PIECES: [ChessPieces; 4] = [ChessPieces::Queen, ChessPieces::Rook, ChessPieces::Bishop, ChessPieces::Knight];
+
+impl ChessBoardState {
+    /// All legal moves of the side to move, including castling, en passant and one move per promotion piece.
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        self.copy_position().generate_legal_moves()
+    }
+
+    /// All legal moves of the piece on the given square, empty if it is not that side's turn.
+    pub fn legal_moves_from(&self, position: ChessBoardPosition) -> Vec<ChessMove> {
+        let mut moves = vec![];
+        if let Some(piece) = self.get_piece_at(position) {
+            if piece.color == self.to_move() {
+                self.copy_position().append_legal_moves_for_piece(&piece, &mut moves);
+            }
+        }
+        moves
+    }
+
+    /// Like legal_moves, but looks ahead with make and undo on this board instead of a copy.
+    pub(crate) fn generate_legal_moves(&mut self) -> Vec<ChessMove> {
+        let mut moves = vec![];
+        for position in Squares(self.placement.color(self.to_move)) {
+            if let Some(piece) = self.get_piece_at(position) {
+                self.append_legal_moves_for_piece(&piece, &mut moves);
+            }
+        }
+        moves
+    }
+
+    /// Filters the candidate moves of the piece by playing and taking back each of them.
+    pub(crate) fn append_legal_moves_for_piece(&mut self, piece: &PositionedChessPiece, moves: &mut Vec<ChessMove>) {
+        for target in Squares(self.get_candidate_targets(piece)) {
+            let is_promotion = piece.piece == ChessPieces::Pawn && (target.row == 0 || target.row == 7);
+            // The promotion piece does not change whether the move is legal, so the queen stands in for all of them.
+            let promotion = if is_promotion { Some(ChessPieces::Queen) } else { None };
+            let candidate = ChessMove { from: piece.position, to: target, promotion, piece: piece.piece, color: piece.color };
+            if !self.is_move_pseudo_legal(candidate) || self.leaves_king_in_check(candidate) {
+                continue;
+            }
+            if is_promotion {
+                for promotion in PROMOTION_PIECES {
+                    moves.push(ChessMove { promotion: Some(promotion), ..candidate });
+                }
+            } else {
+                moves.push(candidate);
+            }
+        }
+    }
+
+    /// The squares the piece could reach by its movement pattern, before checking the full move rules.
+    fn get_candidate_targets(&self, piece: &PositionedChessPiece) -> Bitboard {
+        let index = square_index(piece.position);
+        let targets = match piece.piece {
+            ChessPieces::King => {
+                // The castling targets two squares to the side, if they are on the board.
+                let castling_targets = ((square_bit(piece.position) << 2) | (square_bit(piece.position) >> 2)) & (0xff << (piece.position.row * 8));
+                KING_ATTACKS[index] | castling_targets
+            },
+            ChessPieces::Knight => KNIGHT_ATTACKS[index],
+            ChessPieces::Queen | ChessPieces::Rook | ChessPieces::Bishop => self.sliding_attacks(piece.piece, piece.position),
+            ChessPieces::Pawn => {
+                let single_push = match piece.color {
+                    ChessColors::White => square_bit(piece.position) << 8,
+                    ChessColors::Black => square_bit(piece.position) >> 8,
+                };
+                let double_push = match piece.color {
+                    ChessColors::White => single_push << 8,
+                    ChessColors::Black => single_push >> 8,
+                };
+                PAWN_ATTACKS[color_index(piece.color)][index] | single_push | double_push
+            },
+        };
+        targets & !self.placement.color(piece.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{chess_move, square};
+    use super::*;
+
+    #[test]
+    fn test_start_position_moves() {
+        let board = ChessBoardState::new();
+        assert_eq!(board.legal_moves().len(), 20);
+        assert_eq!(board.legal_moves_from(square("b1")).len(), 2);
+        assert_eq!(board.legal_moves_from(square("e2")).len(), 2);
+        assert!(board.legal_moves_from(square("e7")).is_empty());
+        assert!(board.legal_moves_from(square("e4")).is_empty());
+    }
+
+    #[test]
+    fn test_squares_off_board() {
+        let board = ChessBoardState::new();
+        // Row 0 and column 9 would alias the pawn on b2 without a bounds check.
+        let aliased = ChessBoardPosition { row: 0, column: 9 };
+        assert!(board.legal_moves_from(aliased).is_empty());
+        assert!(board.legal_moves_from(ChessBoardPosition { row: 8, column: 0 }).is_empty());
+        assert!(board.legal_moves_from(ChessBoardPosition { row: 255, column: 255 }).is_empty());
+        let off_board = ChessMove { from: aliased, to: ChessBoardPosition { row: 10, column: 9 }, promotion: None, piece: ChessPieces::Pawn, color: ChessColors::White };
+        assert_eq!(board.to_san(&off_board), "");
+        assert!(!board.is_en_passant(off_board));
+        assert!(!board.is_move_valid(off_board));
+    }
+
+    #[test]
+    fn test_promotion_and_en_passant_moves() {
+        use ChessColors::*;
+        use ChessPieces::*;
+        let mut board = ChessBoardState::new();
+        for next_move in [
+            chess_move(Pawn, White, "b2", "b4"),
+            chess_move(Pawn, Black, "a7", "a5"),
+            chess_move(Pawn, White, "b4", "b5"),
+            chess_move(Pawn, Black, "c7", "c5"),
+        ] {
+            assert!(board.perform_move(next_move).is_ok());
+        }
+        let moves = board.legal_moves_from(square("b5"));
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&chess_move(Pawn, White, "b5", "b6")));
+        assert!(moves.contains(&chess_move(Pawn, White, "b5", "c6")));
+        for next_move in [
+            chess_move(Pawn, White, "b5", "c6"),
+            chess_move(Knight, Black, "g8", "f6"),
+            chess_move(Pawn, White, "c6", "b7"),
+            chess_move(Knight, Black, "f6", "g8"),
+        ] {
+            assert!(board.perform_move(next_move).is_ok());
+        }
+        let moves = board.legal_moves_from(square("b7"));
+        assert_eq!(moves.len(), 8);
+        assert_eq!(moves.iter().filter(|next_move| next_move.to == square("a8") && next_move.promotion == Some(Knight)).count(), 1);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{ChessBoardState, ChessMove};
+
+impl ChessBoardState {
+    /// Counts the leaf nodes of the tree of legal moves of the given depth.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        self.copy_position().perft_nodes(depth)
+    }
+
+    /// Like perft, but split up by the first move.
+    pub fn perft_divide(&self, depth: u32) -> Vec<(ChessMove, u64)> {
+        if depth == 0 {
+            return vec![];
+        }
+        let mut board = self.copy_position();
+        board.generate_legal_moves().into_iter().map(|next_move| {
+            board.make_move(next_move);
+            let nodes = board.perft_nodes(depth - 1);
+            board.undo_move();
+            (next_move, nodes)
+        }).collect()
+    }
+
+    /// Walks the tree with make and undo on this board.
+    fn perft_nodes(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.generate_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for next_move in moves {
+            self.make_move(next_move);
+            nodes += self.perft_nodes(depth - 1);
+            self.undo_move();
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_start_position() {
+        let board = ChessBoardState::new();
+        assert_eq!(board.perft(0), 1);
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.perft(3), 8902);
+        assert_eq!(board.to_fen(), ChessBoardState::new().to_fen());
+    }
+
+    #[test]
+    fn test_perft_divide() {
+        let board = ChessBoardState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let divide = board.perft_divide(2);
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+        let castling = divide.iter().find(|(next_move, _)| next_move.to_string() == "e1g1").unwrap();
+        assert_eq!(castling.1, 43);
+        assert!(board.perft_divide(0).is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{ChessBoardState, ChessMove, ChessPieces, SanError};
+
+fn piece_from_san_character(character: char) -> Option<ChessPieces> {
+    match character {
+        'K' => Some(ChessPieces::King),
+        'Q' => Some(ChessPieces::Queen),
+        'R' => Some(ChessPieces::Rook),
+        'B' => Some(ChessPieces::Bishop),
+        'N' => Some(ChessPieces::Knight),
+        _ => None,
+    }
+}
+
+pub(crate) fn piece_to_san_character(piece: ChessPieces) -> char {
+    match piece {
+        ChessPieces::King => 'K',
+        ChessPieces::Queen => 'Q',
+        ChessPieces::Rook => 'R',
+        ChessPieces::Bishop => 'B',
+        ChessPieces::Knight => 'N',
+        ChessPieces::Pawn => 'P',
+    }
+}
+
+/// The parts of a SAN move that are needed to find the matching legal move.
+struct SanPattern {
+    piece: ChessPieces,
+    from_column: Option<u8>,
+    from_row: Option<u8>,
+    to_column: u8,
+    to_row: u8,
+    promotion: Option<ChessPieces>,
+}
+
+fn parse_san_pattern(text: &str) -> Option<SanPattern> {
+    let mut characters: Vec<char> = text.chars().filter(|&character| character != 'x').collect();
+    let piece = match characters.first().and_then(|&character| piece_from_san_character(character)) {
+        Some(piece) => {
+            characters.remove(0);
+            piece
+        },
+        None => ChessPieces::Pawn,
+    };
+    let mut promotion = None;
+    if piece == ChessPieces::Pawn {
+        if let Some(promotion_piece) = characters.last().and_then(|&character| piece_from_san_character(character)) {
+            characters.pop();
+            if characters.last() == Some(&'=') {
+                characters.pop();
+            }
+            promotion = Some(promotion_piece);
+        }
+    }
+    if characters.len() < 2 || characters.len() > 4 {
+        return None;
+    }
+    let square = characters.split_off(characters.len() - 2);
+    if !('a'..='h').contains(&square[0]) || !('1'..='8').contains(&square[1]) {
+        return None;
+    }
+    let mut pattern = SanPattern { piece, from_column: None, from_row: None, to_column: square[0] as u8 - b'a', to_row: square[1] as u8 - b'1', promotion };
+    for character in characters {
+        if ('a'..='h').contains(&character) && pattern.from_column.is_none() && pattern.from_row.is_none() {
+            pattern.from_column = Some(character as u8 - b'a');
+        } else if ('1'..='8').contains(&character) && pattern.from_row.is_none() {
+            pattern.from_row = Some(character as u8 - b'1');
+        } else {
+            return None;
+        }
+    }
+    Some(pattern)
+}
+
+impl ChessBoardState {
+    /// Finds the legal move for SAN like "Nbd7", "O-O-O" or "e8=Q+", ignoring check suffixes and annotations.
+    pub fn parse_san(&self, san: &str) -> Result<ChessMove, SanError> {
+        let mut text = san.trim();
+        if let Some(stripped) = text.strip_suffix("e.p.") {
+            text = stripped.trim_end();
+        }
+        text = text.trim_end_matches(['+', '#', '!', '?']);
+        let candidates: Vec<ChessMove> = match text {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let kingside = text.len() == 3;
+                self.legal_moves().into_iter().filter(|next_move| next_move.is_castling() && (next_move.to.column == 6) == kingside).collect()
+            },
+            _ => {
+                let pattern = parse_san_pattern(text).ok_or_else(|| SanError::InvalidSyntax(san.to_string()))?;
+                self.legal_moves().into_iter().filter(|next_move| {
+                    next_move.piece == pattern.piece
+                        && next_move.to.column == pattern.to_column
+                        && next_move.to.row == pattern.to_row
+                        && next_move.promotion == pattern.promotion
+                        && pattern.from_column.is_none_or(|column| next_move.from.column == column)
+                        && pattern.from_row.is_none_or(|row| next_move.from.row == row)
+                        && !next_move.is_castling()
+                }).collect()
+            },
+        };
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+
+    /// Formats a legal move in Standard Algebraic Notation with minimal disambiguation and a check or mate suffix.
+    pub fn to_san(&self, next_move: &ChessMove) -> String {
+        let mut san = String::new();
+        if !Self::is_on_board(next_move.from) || !Self::is_on_board(next_move.to) {
+            return san;
+        }
+        if next_move.is_castling() {
+            san.push_str(if next_move.to.column == 6 { "O-O" } else { "O-O-O" });
+        } else {
+            let is_capture = self.get_piece_at(next_move.to).is_some() || self.is_en_passant(*next_move);
+            if next_move.piece == ChessPieces::Pawn {
+                if is_capture {
+                    san.push((b'a' + next_move.from.column) as char);
+                }
+            } else {
+                san.push(piece_to_san_character(next_move.piece));
+                let others: Vec<ChessMove> = self.legal_moves().into_iter().filter(|other| {
+                    other.piece == next_move.piece && other.to == next_move.to && other.from != next_move.from
+                }).collect();
+                if !others.is_empty() {
+                    if others.iter().all(|other| other.from.column != next_move.from.column) {
+                        san.push((b'a' + next_move.from.column) as char);
+                    } else if others.iter().all(|other| other.from.row != next_move.from.row) {
+                        san.push((b'1' + next_move.from.row) as char);
+                    } else {
+                        san.push_str(&next_move.from.to_string());
+                    }
+                }
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&next_move.to.to_string());
+            if let Some(promotion) = next_move.promotion {
+                san.push('=');
+                san.push(piece_to_san_character(promotion));
+            }
+        }
+        if let Ok(outcome) = self.copy_position().perform_move(*next_move) {
+            if outcome.is_checkmate {
+                san.push('#');
+            } else if outcome.is_check {
+                san.push('+');
+            }
+        }
+        san
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{play, square};
+    use crate::ChessColors;
+    use super::*;
+
+    #[test]
+    fn test_parse_san() {
+        let mut board = ChessBoardState::new();
+        play(&mut board, &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O", "Bg4"]);
+        assert_eq!(board.to_fen(), "r2qkbnr/1pp2ppp/p1p5/4p3/4P1b1/5N2/PPPP1PPP/RNBQ1RK1 w kq - 2 6");
+        assert_eq!(board.parse_san("Qe2!?").unwrap().to, square("e2"));
+        assert_eq!(board.parse_san("Ke1"), Err(SanError::IllegalMove("Ke1".to_string())));
+        assert_eq!(board.parse_san("Zz9"), Err(SanError::InvalidSyntax("Zz9".to_string())));
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        let board = ChessBoardState::from_fen("r3k1br/1b5q/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        assert_eq!(board.parse_san("Bd5"), Err(SanError::AmbiguousMove("Bd5".to_string())));
+        assert_eq!(board.parse_san("Bbd5").unwrap().from, square("b7"));
+        let board = ChessBoardState::from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("Nd2"), Err(SanError::AmbiguousMove("Nd2".to_string())));
+        assert_eq!(board.to_san(&board.parse_san("Nbd2").unwrap()), "Nbd2");
+        let board = ChessBoardState::from_fen("6k1/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("Qd4"), Err(SanError::AmbiguousMove("Qd4".to_string())));
+        assert_eq!(board.to_san(&board.parse_san("Qa4d4").unwrap()), "Qa4d4");
+        assert_eq!(board.to_san(&board.parse_san("Q1d4").unwrap()), "Q1d4");
+        assert_eq!(board.to_san(&board.parse_san("Qhd4").unwrap()), "Qhd4");
+    }
+
+    #[test]
+    fn test_special_moves() {
+        let board = ChessBoardState::from_fen("r3k2r/2P5/8/3pP3/8/8/8/4K3 w kq d6 0 1").unwrap();
+        let en_passant = board.parse_san("exd6 e.p.").unwrap();
+        assert_eq!(en_passant.to, square("d6"));
+        assert_eq!(board.to_san(&en_passant), "exd6");
+        let promotion = board.parse_san("c8=Q+").unwrap();
+        assert_eq!(promotion.promotion, Some(ChessPieces::Queen));
+        assert_eq!(board.to_san(&promotion), "c8=Q+");
+        assert_eq!(board.to_san(&board.parse_san("c8N").unwrap()), "c8=N");
+        assert_eq!(board.parse_san("c8"), Err(SanError::IllegalMove("c8".to_string())));
+        let board = ChessBoardState::from_fen("r3k2r/8/8/8/8/8/8/4K3 b kq - 0 1").unwrap();
+        let castling = board.parse_san("O-O-O").unwrap();
+        assert_eq!(castling.to, square("c8"));
+        assert_eq!(castling.color, ChessColors::Black);
+        assert_eq!(board.to_san(&castling), "O-O-O");
+        assert_eq!(board.to_san(&board.parse_san("0-0").unwrap()), "O-O");
+    }
+
+    #[test]
+    fn test_checkmate_suffix() {
+        let board = ChessBoardState::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(board.to_san(&board.parse_san("Ra8").unwrap()), "Ra8#");
+        assert_eq!(board.to_san(&board.parse_san("Ra7").unwrap()), "Ra7");
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1"] {
+            let board = ChessBoardState::from_fen(fen).unwrap();
+            for next_move in board.legal_moves() {
+                assert_eq!(board.parse_san(&board.to_san(&next_move)), Ok(next_move));
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{ChessBoardPosition, ChessBoardState, ChessMove, ChessPieces, UciCastlingFormat, UciError};
+
+fn promotion_from_uci_character(character: char) -> Option<ChessPieces> {
+    match character {
+        'q' => Some(ChessPieces::Queen),
+        'r' => Some(ChessPieces::Rook),
+        'b' => Some(ChessPieces::Bishop),
+        'n' => Some(ChessPieces::Knight),
+        _ => None,
+    }
+}
+
+impl ChessBoardState {
+    /// Finds the legal move for UCI notation like "e2e4" or "e7e8q", accepting castling as "e1g1" or "e1h1".
+    pub fn parse_uci_move(&self, uci: &str) -> Result<ChessMove, UciError> {
+        let text = uci.trim();
+        let invalid = || UciError::InvalidSyntax(uci.to_string());
+        if !text.is_ascii() || (text.len() != 4 && text.len() != 5) {
+            return Err(invalid());
+        }
+        let from = ChessBoardPosition::from_algebraic(&text[0..2]).ok_or_else(invalid)?;
+        let mut to = ChessBoardPosition::from_algebraic(&text[2..4]).ok_or_else(invalid)?;
+        let promotion = match text.chars().nth(4) {
+            Some(character) => Some(promotion_from_uci_character(character).ok_or_else(invalid)?),
+            None => None,
+        };
+        if let (Some(king), Some(rook)) = (self.get_piece_at(from), self.get_piece_at(to)) {
+            if king.piece == ChessPieces::King && rook.piece == ChessPieces::Rook && king.color == rook.color && from.column == 4 && from.row == to.row {
+                to.column = if to.column > from.column { 6 } else { 2 };
+            }
+        }
+        self.legal_moves_from(from)
+            .into_iter()
+            .find(|next_move| next_move.to == to && next_move.promotion == promotion)
+            .ok_or_else(|| UciError::IllegalMove(uci.to_string()))
+    }
+
+    /// Formats a move in UCI notation, writing castling in the given format.
+    pub fn to_uci(&self, next_move: &ChessMove, castling_format: UciCastlingFormat) -> String {
+        if castling_format == UciCastlingFormat::KingTakesRook && next_move.is_castling() {
+            let rook_column = if next_move.to.column > next_move.from.column { 7 } else { 0 };
+            return format!("{}{}", next_move.from, ChessBoardPosition { row: next_move.from.row, column: rook_column });
+        }
+        next_move.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChessColors;
+    use super::*;
+
+    #[test]
+    fn test_parse_uci_move() {
+        let board = ChessBoardState::new();
+        let next_move = board.parse_uci_move("g1f3").unwrap();
+        assert_eq!(next_move.piece, ChessPieces::Knight);
+        assert_eq!(next_move.color, ChessColors::White);
+        assert_eq!(board.parse_uci_move("e2e5"), Err(UciError::IllegalMove("e2e5".to_string())));
+        assert_eq!(board.parse_uci_move("e7e5"), Err(UciError::IllegalMove("e7e5".to_string())));
+        assert_eq!(board.parse_uci_move("e2e4x"), Err(UciError::InvalidSyntax("e2e4x".to_string())));
+        assert_eq!(board.parse_uci_move("e2"), Err(UciError::InvalidSyntax("e2".to_string())));
+    }
+
+    #[test]
+    fn test_special_moves() {
+        let board = ChessBoardState::from_fen("r3k2r/2P5/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let en_passant = board.parse_uci_move("e5d6").unwrap();
+        assert!(board.is_en_passant(en_passant));
+        let promotion = board.parse_uci_move("c7c8q").unwrap();
+        assert_eq!(promotion.promotion, Some(ChessPieces::Queen));
+        assert_eq!(board.to_uci(&promotion, UciCastlingFormat::Standard), "c7c8q");
+        assert_eq!(board.parse_uci_move("c7c8"), Err(UciError::IllegalMove("c7c8".to_string())));
+        let castling = board.parse_uci_move("e1g1").unwrap();
+        assert!(castling.is_castling());
+        assert_eq!(board.parse_uci_move("e1h1"), Ok(castling));
+        assert_eq!(board.to_uci(&castling, UciCastlingFormat::Standard), "e1g1");
+        assert_eq!(board.to_uci(&castling, UciCastlingFormat::KingTakesRook), "e1h1");
+        let castling = board.parse_uci_move("e1a1").unwrap();
+        assert_eq!(castling.to, ChessBoardPosition { row: 0, column: 2 });
+        assert_eq!(board.to_uci(&castling, UciCastlingFormat::KingTakesRook), "e1a1");
+    }
+}
@@ -0,0 +1,186 @@
+use crate::board_state::bitboard::{color_index, piece_index, square_index, PAWN_ATTACKS};
+use crate::{ChessBoardPosition, ChessBoardState, ChessColors, ChessPieces, ZobristKeys};
+use std::sync::{Arc, OnceLock};
+
+mod polyglot;
+
+pub use polyglot::POLYGLOT_ZOBRIST_KEYS;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+/// The keys boards use unless others are set with [`ChessBoardState::set_zobrist_keys`].
+pub static DEFAULT_ZOBRIST_KEYS: ZobristKeys = ZobristKeys::from_seed(0x2545_f491_4f6c_dd1d);
+
+/// The default keys, shared by all boards instead of copied into each.
+pub(crate) fn default_zobrist_keys() -> Arc<ZobristKeys> {
+    static KEYS: OnceLock<Arc<ZobristKeys>> = OnceLock::new();
+    Arc::clone(KEYS.get_or_init(|| Arc::new(DEFAULT_ZOBRIST_KEYS.clone())))
+}
+
+impl ZobristKeys {
+    /// Generates the keys with the SplitMix64 generator from the given seed.
+    pub const fn from_seed(seed: u64) -> ZobristKeys {
+        let mut keys = [0; 781];
+        let mut state = seed;
+        let mut index = 0;
+        while index < keys.len() {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut mixed = state;
+            mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            keys[index] = mixed ^ (mixed >> 31);
+            index += 1;
+        }
+        ZobristKeys { keys }
+    }
+
+    /// Takes the keys from a table laid out like the Polyglot Random64 table.
+    pub const fn from_polyglot_table(table: &[u64; 781]) -> ZobristKeys {
+        ZobristKeys { keys: *table }
+    }
+
+    fn piece_key(&self, piece: ChessPieces, color: ChessColors, position: ChessBoardPosition) -> u64 {
+        let kind = piece_index(piece) * 2 + if color == ChessColors::White { 1 } else { 0 };
+        self.keys[64 * kind + square_index(position)]
+    }
+}
+
+impl ChessBoardState {
+    /// A 64 bit hash of the position, kept up to date with every move and undo.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+
+    /// Switches to other keys, e.g. a copy of [`POLYGLOT_ZOBRIST_KEYS`], and recomputes the key of the current position.
+    pub fn set_zobrist_keys(&mut self, keys: impl Into<Arc<ZobristKeys>>) {
+        self.zobrist_keys = keys.into();
+        self.zobrist_key = self.compute_zobrist_key();
+    }
+
+    /// Computes the key of the position from scratch.
+    pub(crate) fn compute_zobrist_key(&self) -> u64 {
+        self.pieces().fold(self.zobrist_state_key(), |key, piece| key ^ self.zobrist_keys.piece_key(piece.piece, piece.color, piece.position))
+    }
+
+    /// Adds a piece to the key or removes it again.
+    pub(crate) fn toggle_zobrist_piece(&mut self, piece: ChessPieces, color: ChessColors, position: ChessBoardPosition) {
+        self.zobrist_key ^= self.zobrist_keys.piece_key(piece, color, position);
+    }
+
+    /// The part of the key that does not depend on the pieces, hashing the en passant file only if a capture is possible.
+    pub(crate) fn zobrist_state_key(&self) -> u64 {
+        let keys = &self.zobrist_keys.keys;
+        let mut key = 0;
+        let rights = [
+            !self.white_castling_state.king_moved && !self.white_castling_state.rook_h_moved,
+            !self.white_castling_state.king_moved && !self.white_castling_state.rook_a_moved,
+            !self.black_castling_state.king_moved && !self.black_castling_state.rook_h_moved,
+            !self.black_castling_state.king_moved && !self.black_castling_state.rook_a_moved,
+        ];
+        for (index, _) in rights.iter().enumerate().filter(|(_, &right)| right) {
+            key ^= keys[CASTLING_OFFSET + index];
+        }
+        if let Some(target) = self.en_passant_target {
+            // The pawns that could capture en passant are those that attack the target square.
+            let capturing_pawns = PAWN_ATTACKS[color_index(self.to_move.opponent())][square_index(target)] & self.placement.pieces_of(ChessPieces::Pawn, self.to_move);
+            if capturing_pawns != 0 {
+                key ^= keys[EN_PASSANT_OFFSET + target.column as usize];
+            }
+        }
+        if self.to_move == ChessColors::White {
+            key ^= keys[TURN_OFFSET];
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::play;
+    use super::*;
+
+    #[test]
+    fn test_incremental_updates() {
+        let mut board = ChessBoardState::from_fen("r3k2r/1P6/8/8/5p2/8/4P3/R3K2R w KQkq - 0 1").unwrap();
+        let start_key = board.zobrist_key();
+        play(&mut board, &["e4", "fxe3", "O-O-O", "O-O", "b8=N", "Raxb8"]);
+        while board.undo_move().is_some() {
+            assert_eq!(board.zobrist_key(), board.compute_zobrist_key());
+        }
+        assert_eq!(board.zobrist_key(), start_key);
+    }
+
+    #[test]
+    fn test_equal_positions() {
+        let mut board_1 = ChessBoardState::new();
+        let mut board_2 = ChessBoardState::new();
+        play(&mut board_1, &["Nf3", "Nf6", "Nc3"]);
+        play(&mut board_2, &["Nc3", "Nf6", "Nf3"]);
+        assert_eq!(board_1.zobrist_key(), board_2.zobrist_key());
+        assert_ne!(board_1.zobrist_key(), ChessBoardState::new().zobrist_key());
+        assert_eq!(board_1.zobrist_key(), ChessBoardState::from_fen(&board_1.to_fen()).unwrap().zobrist_key());
+        // Only the castling rights differ.
+        play(&mut board_1, &["Nc6", "Rg1", "Nb8", "Rh1", "Nc6"]);
+        play(&mut board_2, &["Nc6"]);
+        assert_eq!(board_1.to_fen().split(' ').next(), board_2.to_fen().split(' ').next());
+        assert_ne!(board_1.zobrist_key(), board_2.zobrist_key());
+    }
+
+    #[test]
+    fn test_en_passant_file() {
+        // The double push only changes the key by the en passant file if the pawn can be captured.
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        play(&mut board, &["e4"]);
+        assert_ne!(board.zobrist_key(), ChessBoardState::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap().zobrist_key());
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/2p5/8/4P3/4K3 w - - 0 1").unwrap();
+        play(&mut board, &["e4"]);
+        assert_eq!(board.zobrist_key(), ChessBoardState::from_fen("4k3/8/8/8/2p1P3/8/8/4K3 b - - 0 1").unwrap().zobrist_key());
+    }
+
+    #[test]
+    fn test_polyglot_layout() {
+        let mut table = [0; 781];
+        // The white pawn on e4, the black king on e8, white castling kingside and the en passant file e.
+        table[64 + 8 * 3 + 4] = 1;
+        table[64 * 10 + 8 * 7 + 4] = 2;
+        table[768] = 4;
+        table[772 + 4] = 8;
+        table[780] = 16;
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/3p4/8/4P3/4K2R w K - 0 1").unwrap();
+        board.set_zobrist_keys(ZobristKeys::from_polyglot_table(&table));
+        assert_eq!(board.zobrist_key(), 2 | 4 | 16);
+        play(&mut board, &["e4"]);
+        assert_eq!(board.zobrist_key(), 1 | 2 | 4 | 8);
+    }
+
+    #[test]
+    fn test_polyglot_keys() {
+        // The keys published with the Polyglot book format for the positions after the moves.
+        let cases = [
+            ("", 0x463b_9618_1691_fc9c),
+            ("e2e4", 0x823c_9b50_fd11_4196),
+            ("e2e4 d7d5", 0x0756_b944_61c5_0fb0),
+            ("e2e4 d7d5 e4e5", 0x662f_afb9_65db_29d4),
+            ("e2e4 d7d5 e4e5 f7f5", 0x22a4_8b5a_8e47_ff78),
+            ("e2e4 d7d5 e4e5 f7f5 e1e2", 0x652a_607c_a3f2_42c1),
+            ("e2e4 d7d5 e4e5 f7f5 e1e2 e8f7", 0x00fd_d303_c946_bdd9),
+            ("a2a4 b7b5 h2h4 b5b4 c2c4", 0x3c81_23ea_7b06_7637),
+            ("a2a4 b7b5 h2h4 b5b4 c2c4 b4c3 a1a3", 0x5c3f_9b82_9b27_9560),
+        ];
+        let keys = Arc::new(POLYGLOT_ZOBRIST_KEYS.clone());
+        for (moves, expected_key) in cases {
+            let mut board = ChessBoardState::new();
+            board.set_zobrist_keys(Arc::clone(&keys));
+            for uci in moves.split_whitespace() {
+                let next_move = board.parse_uci_move(uci).unwrap();
+                assert!(board.perform_move(next_move).is_ok());
+            }
+            assert_eq!(board.zobrist_key(), expected_key, "after '{}'", moves);
+            let mut from_fen = ChessBoardState::from_fen(&board.to_fen()).unwrap();
+            from_fen.set_zobrist_keys(Arc::clone(&keys));
+            assert_eq!(from_fen.zobrist_key(), expected_key);
+        }
+    }
+}
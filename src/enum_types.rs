@@ -1 +1,3 @@
-pub mod enum_types;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod enum_types;
+pub mod error_types;
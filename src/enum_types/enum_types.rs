@@ -1,5 +1,5 @@
 /// The piece types of a chess game.
-#[derive( PartialEq, Eq, Copy, Clone)]
+#[derive( PartialEq, Eq, Copy, Clone, Debug, Hash)]
 pub enum ChessPieces {
     King,
     Queen,
@@ -10,8 +10,70 @@ pub enum ChessPieces {
 }
 
 /// For readability, we don't want to use boolean values (is_player_white == true) to determine the color of a piece. Instead, we use this enum.
-#[derive( PartialEq, Eq, Clone, Copy)]
+#[derive( PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum ChessColors {
     Black,
     White,
 }
+
+/// Decides whether the en passant square is written after every double push or only if a capture is possible.
+#[derive( PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum FenEnPassantMode {
+    Always,
+    OnlyWhenCapturable,
+}
+
+/// Decides whether castling is written in UCI as "e1g1" or, like Chess960, as "e1h1".
+#[derive( PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum UciCastlingFormat {
+    Standard,
+    KingTakesRook,
+}
+
+/// The reason a game ended in a draw.
+#[derive( PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum DrawReason {
+    /// The side to move has no legal move but is not in check.
+    Stalemate,
+    /// The same position occurred for the fifth time, which ends the game without a claim.
+    FivefoldRepetition,
+    /// 75 moves by each side were played without a pawn move or a capture.
+    SeventyFiveMoveRule,
+    /// Neither side has enough material left to checkmate.
+    InsufficientMaterial,
+}
+
+/// The state of a game as determined by the position on the board.
+#[derive( PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate { winner: ChessColors },
+    Draw(DrawReason),
+}
+
+impl GameStatus {
+    /// Returns true if the game has ended, either by checkmate or by a draw.
+    pub fn is_game_over(self) -> bool {
+        self != GameStatus::Ongoing
+    }
+
+    /// The result token used in PGN: "1-0", "0-1", "1/2-1/2", or "*" for a game that is still running.
+    pub fn result_token(self) -> &'static str {
+        match self {
+            GameStatus::Ongoing => "*",
+            GameStatus::Checkmate { winner: ChessColors::White } => "1-0",
+            GameStatus::Checkmate { winner: ChessColors::Black } => "0-1",
+            GameStatus::Draw(_) => "1/2-1/2",
+        }
+    }
+}
+
+impl ChessColors {
+    /// The color of the other player.
+    pub fn opponent(self) -> ChessColors {
+        match self {
+            ChessColors::White => ChessColors::Black,
+            ChessColors::Black => ChessColors::White,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{ChessBoardPosition, ChessColors, ChessMove, ChessPieces, DrawReason};
+use std::fmt;
+
+/// Describes why a FEN string could not be turned into a board state.
+#[derive( PartialEq, Eq, Clone, Debug)]
+pub enum FenError {
+    /// A FEN string consists of exactly six fields separated by whitespace.
+    WrongNumberOfFields(usize),
+    /// The piece placement has to describe exactly eight ranks.
+    WrongNumberOfRanks(usize),
+    /// A rank (1 to 8) does not describe exactly eight squares.
+    InvalidRankLength(u8),
+    /// A rank (1 to 8) has two digits in a row, e.g. "44" instead of "8".
+    ConsecutiveDigits(u8),
+    InvalidPieceCharacter(char),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassantSquare(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    MissingKing(ChessColors),
+    TooManyKings(ChessColors),
+    TooManyPieces(ChessColors),
+    TooManyPawns(ChessColors),
+    PawnOnBackRank(ChessBoardPosition),
+    /// The side that just moved cannot have left its own king in check.
+    SideNotToMoveInCheck(ChessColors),
+    /// A castling right is given although the king or the rook is not on its original square.
+    CastlingRightsInconsistent(char),
+    /// The en passant square does not belong to a pawn that just performed a double push.
+    ImplausibleEnPassantSquare(ChessBoardPosition),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongNumberOfFields(count) => write!(f, "expected 6 fields, found {}", count),
+            FenError::WrongNumberOfRanks(count) => write!(f, "expected 8 ranks in the piece placement, found {}", count),
+            FenError::InvalidRankLength(rank) => write!(f, "rank {} does not describe exactly 8 squares", rank),
+            FenError::ConsecutiveDigits(rank) => write!(f, "rank {} has two digits in a row", rank),
+            FenError::InvalidPieceCharacter(character) => write!(f, "invalid character '{}' in the piece placement", character),
+            FenError::InvalidSideToMove(field) => write!(f, "invalid side to move '{}'", field),
+            FenError::InvalidCastlingRights(field) => write!(f, "invalid castling rights '{}'", field),
+            FenError::InvalidEnPassantSquare(field) => write!(f, "invalid en passant square '{}'", field),
+            FenError::InvalidHalfmoveClock(field) => write!(f, "invalid halfmove clock '{}'", field),
+            FenError::InvalidFullmoveNumber(field) => write!(f, "invalid fullmove number '{}'", field),
+            FenError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            FenError::TooManyKings(color) => write!(f, "{:?} has more than one king", color),
+            FenError::TooManyPieces(color) => write!(f, "{:?} has more than 16 pieces", color),
+            FenError::TooManyPawns(color) => write!(f, "{:?} has more than 8 pawns", color),
+            FenError::PawnOnBackRank(position) => write!(f, "pawn on {} cannot stand on the first or last rank", position),
+            FenError::SideNotToMoveInCheck(color) => write!(f, "{:?} is in check but it is not their turn", color),
+            FenError::CastlingRightsInconsistent(right) => write!(f, "castling right '{}' requires king and rook on their original squares", right),
+            FenError::ImplausibleEnPassantSquare(position) => write!(f, "en passant square {} does not follow a double pawn push", position),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// Describes why a move cannot be performed on the board.
+#[derive( PartialEq, Eq, Clone, Debug)]
+pub enum MoveError {
+    /// The game already ended in a draw that no move can change.
+    GameOver(DrawReason),
+    /// The start or target square lies outside of the board.
+    SquareOffBoard(ChessBoardPosition),
+    /// There is no piece on the start square.
+    NoPieceOnSquare(ChessBoardPosition),
+    /// The piece on the start square is not the one named in the move.
+    PieceMismatch { position: ChessBoardPosition, piece: ChessPieces, color: ChessColors },
+    /// The move is made with a piece of the side that is not to move.
+    WrongSideToMove(ChessColors),
+    /// A pawn reaches the last rank, but the move does not say which piece it becomes.
+    MissingPromotion(ChessMove),
+    /// Pawns can only be promoted to a queen, rook, bishop or knight.
+    InvalidPromotionPiece(ChessPieces),
+    /// The move names a promotion piece, but it is not a pawn reaching the last rank.
+    UnexpectedPromotion(ChessMove),
+    /// The piece cannot move like this: wrong shape, blocked way or a target occupied by an own piece.
+    IllegalMovement(ChessMove),
+    /// The king or rook has moved, the way is blocked or the king is in check or passes an attacked square.
+    CastlingNotAllowed(ChessMove),
+    /// The move would leave the own king in check.
+    LeavesKingInCheck(ChessMove),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::GameOver(reason) => write!(f, "the game is already drawn ({:?})", reason),
+            MoveError::SquareOffBoard(position) => write!(f, "the square in row {} and column {} is not on the board", position.row, position.column),
+            MoveError::NoPieceOnSquare(position) => write!(f, "there is no piece on {}", position),
+            MoveError::PieceMismatch { position, piece, color } => write!(f, "the piece on {} is a {:?} {:?}", position, color, piece),
+            MoveError::WrongSideToMove(color) => write!(f, "{:?} is not to move", color),
+            MoveError::MissingPromotion(next_move) => write!(f, "{} needs a promotion piece", next_move),
+            MoveError::InvalidPromotionPiece(piece) => write!(f, "a pawn cannot be promoted to a {:?}", piece),
+            MoveError::UnexpectedPromotion(next_move) => write!(f, "{} is not a promotion", next_move),
+            MoveError::IllegalMovement(next_move) => write!(f, "the piece cannot move from {} to {}", next_move.from, next_move.to),
+            MoveError::CastlingNotAllowed(next_move) => write!(f, "castling with {} is not allowed", next_move),
+            MoveError::LeavesKingInCheck(next_move) => write!(f, "{} leaves the own king in check", next_move),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Describes why a move in Standard Algebraic Notation could not be matched to a move on the board.
+#[derive( PartialEq, Eq, Clone, Debug)]
+pub enum SanError {
+    /// The text is not a move in Standard Algebraic Notation.
+    InvalidSyntax(String),
+    /// No legal move in the current position matches the text.
+    IllegalMove(String),
+    /// More than one legal move matches the text, so the origin square has to be specified.
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "'{}' is not a valid SAN move", san),
+            SanError::IllegalMove(san) => write!(f, "'{}' is not a legal move in this position", san),
+            SanError::AmbiguousMove(san) => write!(f, "'{}' matches more than one legal move", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+/// Describes why a move in UCI long algebraic notation could not be matched to a move on the board.
+#[derive( PartialEq, Eq, Clone, Debug)]
+pub enum UciError {
+    /// The text is not of the form "e2e4" or "e7e8q".
+    InvalidSyntax(String),
+    /// No legal move in the current position matches the text.
+    IllegalMove(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciError::InvalidSyntax(uci) => write!(f, "'{}' is not a valid UCI move", uci),
+            UciError::IllegalMove(uci) => write!(f, "'{}' is not a legal move in this position", uci),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+/// Describes why a game in Portable Game Notation could not be read.
+#[derive( PartialEq, Eq, Clone, Debug)]
+pub enum PgnError {
+    /// Reading from the underlying source failed.
+    Io(String),
+    /// A line in the tag section is not of the form [Name "Value"].
+    InvalidTag { line: usize, text: String },
+    /// The FEN tag does not describe a valid position.
+    InvalidFen(FenError),
+    /// A move of the mainline could not be played, with the ply counted from 1.
+    IllegalMove { ply: usize, token: String, error: SanError },
+    /// A move of the mainline was recognized but the board refused to perform it.
+    MoveRejected { ply: usize, token: String, error: MoveError },
+    /// A closing parenthesis without a matching opening one.
+    UnbalancedVariation { line: usize },
+    /// The input ended inside a comment or a variation.
+    UnexpectedEnd,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Io(message) => write!(f, "failed to read PGN: {}", message),
+            PgnError::InvalidTag { line, text } => write!(f, "invalid tag '{}' in line {}", text, line),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::IllegalMove { ply, token, error } => write!(f, "illegal move '{}' at ply {}: {}", token, ply, error),
+            PgnError::MoveRejected { ply, token, error } => write!(f, "move '{}' at ply {} was rejected: {}", token, ply, error),
+            PgnError::UnbalancedVariation { line } => write!(f, "unbalanced variation in line {}", line),
+            PgnError::UnexpectedEnd => write!(f, "input ended inside a comment or variation"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
@@ -0,0 +1,33 @@
+//! An independent implementation of the game of chess.
+//!
+//! The central type is [`ChessBoardState`], which holds a position and the moves that led to it. Moves are
+//! described by [`ChessMove`] and applied with [`ChessBoardState::perform_move`]. The state of the board can be
+//! exported as FEN with [`ChessBoardState::to_fen`] and loaded from FEN with [`ChessBoardState::from_fen`].
+//!
+//! ```
+//! use chess_library::*;
+//!
+//! let mut board = ChessBoardState::new();
+//! board.perform_move(ChessMove {
+//!     from: ChessBoardPosition { row: 1, column: 4 },
+//!     to: ChessBoardPosition { row: 3, column: 4 },
+//!     promotion: None,
+//!     piece: ChessPieces::Pawn,
+//!     color: ChessColors::White,
+//! }).unwrap();
+//! assert_eq!(board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+//! ```
+
+pub mod board_state;
+pub mod enum_types;
+pub mod pgn;
+pub mod structs;
+#[cfg(test)]
+mod test_helpers;
+
+pub use board_state::board_state::*;
+pub use board_state::zobrist::{DEFAULT_ZOBRIST_KEYS, POLYGLOT_ZOBRIST_KEYS};
+pub use enum_types::enum_types::*;
+pub use enum_types::error_types::*;
+pub use pgn::pgn_reader::*;
+pub use structs::structs::*;
@@ -0,0 +1,2 @@
+pub mod pgn_reader;
+pub mod pgn_writer;
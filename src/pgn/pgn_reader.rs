@@ -0,0 +1,405 @@
+use crate::{ChessBoardState, ChessMove, PgnError, PgnGame};
+use std::io::BufRead;
+
+const RESULT_TOKENS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Reads games in Portable Game Notation from any buffered source, replaying every mainline move.
+///
+/// ```
+/// use chess_library::*;
+///
+/// let pgn = "[Event \"Example\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 {threatening mate} Nc6 (2... g6) 3. Bc4 Nf6?? 4. Qxf7# 1-0\n";
+/// let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes()).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(games[0].moves.len(), 7);
+/// assert!(games[0].board.is_checkmate());
+/// ```
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line_number: usize,
+    pending_line: Option<String>,
+    failed: bool,
+}
+
+/// The state of the game that is currently being read.
+#[derive(Default)]
+struct GameInProgress {
+    tags: Vec<(String, String)>,
+    board: Option<ChessBoardState>,
+    moves: Vec<ChessMove>,
+    result: Option<String>,
+    in_comment: bool,
+    variation_depth: usize,
+    has_content: bool,
+    in_movetext: bool,
+    finished: bool,
+    error: Option<PgnError>,
+}
+
+/// Returns true for move annotations written apart from the move, like "!" or "?!".
+fn is_annotation_glyph(token: &str) -> bool {
+    token.chars().all(|character| character == '!' || character == '?')
+}
+
+/// Splits a line into its first tag and the text after it, ignoring brackets inside the quoted value.
+fn split_tag(text: &str) -> (&str, &str) {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (index, character) in text.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ']' if !in_quotes => return text.split_at(index + 1),
+            _ => {},
+        }
+    }
+    (text, "")
+}
+
+/// Parses a tag pair of the form [Name "Value"], where the value may contain escaped quotes and backslashes.
+fn parse_tag(text: &str) -> Option<(String, String)> {
+    let inner = text.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, rest) = inner.split_once(char::is_whitespace)?;
+    if name.is_empty() || !name.chars().all(|character| character.is_ascii_alphanumeric() || character == '_') {
+        return None;
+    }
+    let quoted = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut characters = quoted.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => value.push(characters.next()?),
+            '"' => return None,
+            _ => value.push(character),
+        }
+    }
+    Some((name.to_string(), value))
+}
+
+impl GameInProgress {
+    fn read_tag(&mut self, text: &str, line_number: usize) {
+        match parse_tag(text) {
+            Some(tag) => self.tags.push(tag),
+            None => self.set_error(PgnError::InvalidTag { line: line_number, text: text.to_string() }),
+        }
+    }
+
+    fn set_error(&mut self, error: PgnError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    /// The board the mainline is replayed on, created once the tag section is over.
+    fn board(&mut self) -> Result<&mut ChessBoardState, PgnError> {
+        if self.board.is_none() {
+            let board = match self.tags.iter().find(|(name, _)| name == "FEN") {
+                Some((_, fen)) => ChessBoardState::from_fen(fen).map_err(PgnError::InvalidFen)?,
+                None => ChessBoardState::new(),
+            };
+            self.board = Some(board);
+        }
+        Ok(self.board.as_mut().unwrap())
+    }
+
+    /// Reads the movetext in the line and returns the text after the result token, if the game ended within the line.
+    fn read_movetext(&mut self, line: &str, line_number: usize) -> Option<String> {
+        let characters: Vec<char> = line.chars().collect();
+        let mut index = 0;
+        while index < characters.len() && !self.finished {
+            let character = characters[index];
+            if self.in_comment {
+                if character == '}' {
+                    self.in_comment = false;
+                }
+                index += 1;
+                continue;
+            }
+            match character {
+                '{' => self.in_comment = true,
+                ';' => return None,
+                '(' => self.variation_depth += 1,
+                ')' => {
+                    if self.variation_depth == 0 {
+                        self.set_error(PgnError::UnbalancedVariation { line: line_number });
+                    } else {
+                        self.variation_depth -= 1;
+                    }
+                },
+                _ if character.is_whitespace() => {},
+                _ => {
+                    let start = index;
+                    while index < characters.len() && !characters[index].is_whitespace() && !"{}();".contains(characters[index]) {
+                        index += 1;
+                    }
+                    let token: String = characters[start..index].iter().collect();
+                    self.read_token(&token);
+                    continue;
+                },
+            }
+            index += 1;
+        }
+        let rest: String = characters[index..].iter().collect();
+        let rest = rest.trim();
+        (self.finished && !rest.is_empty() && !rest.starts_with(';')).then(|| rest.to_string())
+    }
+
+    fn read_token(&mut self, token: &str) {
+        if self.variation_depth > 0 || token.starts_with('$') || token == "e.p." || is_annotation_glyph(token) {
+            return;
+        }
+        if RESULT_TOKENS.contains(&token) {
+            self.result = Some(token.to_string());
+            self.finished = true;
+            return;
+        }
+        let san = token.trim_start_matches(|character: char| character.is_ascii_digit());
+        let san = if san.len() < token.len() && san.starts_with('.') { san.trim_start_matches('.') } else { token };
+        if san.is_empty() || self.error.is_some() {
+            return;
+        }
+        let ply = self.moves.len() + 1;
+        let result = self.board().and_then(|board| {
+            let next_move = board.parse_san(san).map_err(|error| PgnError::IllegalMove { ply, token: token.to_string(), error })?;
+            board.perform_move(next_move).map_err(|error| PgnError::MoveRejected { ply, token: token.to_string(), error })?;
+            Ok(next_move)
+        });
+        match result {
+            Ok(next_move) => self.moves.push(next_move),
+            Err(error) => self.set_error(error),
+        }
+    }
+
+    fn finish(mut self) -> Result<PgnGame, PgnError> {
+        if self.error.is_none() && (self.in_comment || self.variation_depth > 0) {
+            self.error = Some(PgnError::UnexpectedEnd);
+        }
+        if self.error.is_none() {
+            if let Err(error) = self.board() {
+                self.error = Some(error);
+            }
+        }
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(PgnGame { tags: self.tags, moves: self.moves, result: self.result, board: self.board.unwrap() }),
+        }
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    /// Creates a reader for the games in the given source.
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader { reader, line_number: 0, pending_line: None, failed: false }
+    }
+
+    /// Reads the next line, replacing bytes that are not valid UTF-8 so that files in other encodings can still be read.
+    fn next_line(&mut self) -> Result<Option<String>, PgnError> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line));
+        }
+        if self.failed {
+            return Ok(None);
+        }
+        let mut bytes = Vec::new();
+        match self.reader.read_until(b'\n', &mut bytes) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.line_number += 1;
+                let line = String::from_utf8_lossy(&bytes);
+                let line = if self.line_number == 1 { line.trim_start_matches('\u{feff}') } else { &line };
+                Ok(Some(line.to_string()))
+            },
+            Err(error) => Err(PgnError::Io(error.to_string())),
+        }
+    }
+}
+
+/// Yields one result per game, reading a broken game to its end so the following games can still be read.
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut game = GameInProgress::default();
+        let mut read_failed = false;
+        loop {
+            let line = match self.next_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(error) => {
+                    // Keep reading the broken game to its end, but give up on a source that fails repeatedly.
+                    self.failed = read_failed;
+                    read_failed = true;
+                    game.has_content = true;
+                    game.set_error(error);
+                    continue;
+                },
+            };
+            let trimmed = line.trim();
+            if !game.in_comment && game.variation_depth == 0 {
+                if trimmed.starts_with('%') {
+                    continue;
+                }
+                if trimmed.starts_with('[') {
+                    if game.in_movetext {
+                        self.pending_line = Some(line);
+                        break;
+                    }
+                    game.has_content = true;
+                    let (tag, rest) = split_tag(trimmed);
+                    game.read_tag(tag, self.line_number);
+                    if !rest.trim().is_empty() {
+                        self.pending_line = Some(rest.to_string());
+                    }
+                    continue;
+                }
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            game.has_content = true;
+            game.in_movetext = true;
+            self.pending_line = game.read_movetext(&line, self.line_number);
+            if game.finished {
+                break;
+            }
+        }
+        if !game.has_content {
+            return None;
+        }
+        Some(game.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChessColors, GameStatus, SanError};
+    use super::*;
+    use std::io::{self, BufReader, Read};
+
+    /// Returns the given chunks one read at a time, failing for every chunk that is None.
+    struct ChunkedReader(Vec<Option<&'static [u8]>>);
+
+    impl Read for ChunkedReader {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            let chunk = self.0.remove(0).ok_or_else(|| io::Error::other("disconnected"))?;
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    const GAMES: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "Someone \"quoted\""]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+[Event "Variations"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+% an escaped line
+1. e4 $1 (1. e3 {quiet} (1. Kd2) Kd7) 1... Kd7 ; a line comment
+2. Kd2 {a comment
+spanning lines} Kd6 *
+"#;
+
+    #[test]
+    fn test_read_games() {
+        let games: Vec<PgnGame> = PgnReader::new(GAMES.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(games[0].tag("Annotator"), Some("Someone \"quoted\""));
+        assert_eq!(games[0].tags.len(), 8);
+        assert_eq!(games[0].moves.len(), 85);
+        assert_eq!(games[0].result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(games[0].board.to_fen(), "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43");
+        assert_eq!(games[1].moves.len(), 4);
+        assert_eq!(games[1].result.as_deref(), Some("*"));
+        assert_eq!(games[1].board.to_fen(), "8/8/3k4/8/4P3/8/3K4/8 w - - 3 3");
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let pgn = "[Event \"Broken\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n\n[Event \"Fine\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::IllegalMove { ply: 3, token: "Ke3".to_string(), error: SanError::IllegalMove("Ke3".to_string()) }));
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.board.game_status(), GameStatus::Checkmate { winner: ChessColors::Black });
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_separate_annotation_glyphs() {
+        let pgn = "1. e4 ! e5 ?! 2. Nf3 !! Nc6 ?? 3. Bb5 !? a6 ? *\n";
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(game.moves.len(), 6);
+        assert_eq!(game.board.to_fen(), "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4");
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let mut reader = PgnReader::new("[Event Broken]\n1. e4 *\n".as_bytes());
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::InvalidTag { line: 1, text: "[Event Broken]".to_string() }));
+        let mut reader = PgnReader::new("1. e4 ) e5 *\n".as_bytes());
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::UnbalancedVariation { line: 1 }));
+        let mut reader = PgnReader::new("1. e4 {unterminated\n".as_bytes());
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::UnexpectedEnd));
+        let mut reader = PgnReader::new("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n\n*\n".as_bytes());
+        assert!(matches!(reader.next(), Some(Err(PgnError::InvalidFen(_)))));
+        assert!(PgnReader::new("\n\n".as_bytes()).next().is_none());
+    }
+
+    #[test]
+    fn test_text_after_result_and_tags() {
+        let pgn = "[Event \"a]b\"] [Site \"c\"]\n\n1. e4 1-0 [Event \"Next\"] 1. d4 * ; done\n";
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tags, vec![("Event".to_string(), "a]b".to_string()), ("Site".to_string(), "c".to_string())]);
+        assert_eq!(games[0].result.as_deref(), Some("1-0"));
+        assert_eq!(games[1].tag("Event"), Some("Next"));
+        assert_eq!(games[1].moves.len(), 1);
+        assert_eq!(games[1].result.as_deref(), Some("*"));
+        let mut reader = PgnReader::new("[Event \"a\"] [Site b]\n1. e4 *\n".as_bytes());
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::InvalidTag { line: 1, text: "[Site b]".to_string() }));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_non_utf8_input() {
+        let pgn: &[u8] = b"[Event \"Latin-1\"]\n[White \"M\xfcller\"]\n\n1. e4 *\n\n[Event \"Next\"]\n\n1. d4 *\n";
+        let games: Vec<PgnGame> = PgnReader::new(pgn).collect::<Result<_, _>>().unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("White"), Some("M\u{fffd}ller"));
+        assert_eq!(games[1].tag("Event"), Some("Next"));
+        assert_eq!(games[1].moves.len(), 1);
+    }
+
+    #[test]
+    fn test_read_error() {
+        let source = ChunkedReader(vec![Some(b"[Event \"Broken\"]\n\n1. e4 e5 "), None, Some(b"2. Nf3 Nc6 1-0\n\n[Event \"Fine\"]\n\n1. d4 *\n")]);
+        let mut reader = PgnReader::new(BufReader::new(source));
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::Io("disconnected".to_string())));
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.tag("Event"), Some("Fine"));
+        assert_eq!(game.moves.len(), 1);
+        assert!(reader.next().is_none());
+        let mut reader = PgnReader::new(BufReader::new(ChunkedReader(vec![None; 100])));
+        assert_eq!(reader.next().unwrap().err(), Some(PgnError::Io("disconnected".to_string())));
+        assert!(reader.next().is_none());
+    }
+}
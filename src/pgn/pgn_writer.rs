@@ -0,0 +1,115 @@
+use crate::{ChessBoardState, ChessColors};
+
+const MAX_LINE_LENGTH: usize = 80;
+const SEVEN_TAG_ROSTER: [(&str, &str); 6] = [("Event", "?"), ("Site", "?"), ("Date", "????.??.??"), ("Round", "?"), ("White", "?"), ("Black", "?")];
+
+fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Joins the tokens with single spaces, starting a new line whenever the next token would exceed the maximum line length.
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length += token.len();
+    }
+    text
+}
+
+impl ChessBoardState {
+    /// Exports the game as PGN with the seven tag roster, the given tags and a FEN tag for non-standard starts.
+    pub fn to_pgn(&self, tags: &[(&str, &str)]) -> String {
+        let result = self.game_status().result_token();
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = tags.iter().find(|(tag_name, _)| *tag_name == name).map_or(default, |(_, value)| value);
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escape_tag_value(value)));
+        }
+        pgn.push_str(&format!("[Result \"{}\"]\n", result));
+        if let Some(fen) = self.initial_fen() {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", fen));
+        }
+        for (name, value) in tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|(roster_name, _)| roster_name == name) && !["Result", "SetUp", "FEN"].contains(name) {
+                pgn.push_str(&format!("[{} \"{}\"]\n", name, escape_tag_value(value)));
+            }
+        }
+        pgn.push('\n');
+        let mut board = match self.initial_fen() {
+            Some(fen) => ChessBoardState::from_fen(fen).expect("the initial FEN was valid when the board was created"),
+            None => ChessBoardState::new(),
+        };
+        let mut tokens = vec![];
+        for (index, next_move) in self.move_history().iter().enumerate() {
+            if board.to_move() == ChessColors::White {
+                tokens.push(format!("{}.", board.fullmove_number()));
+            } else if index == 0 {
+                tokens.push(format!("{}...", board.fullmove_number()));
+            }
+            tokens.push(board.to_san(next_move));
+            board.perform_move(*next_move).expect("the moves in the history were legal when they were played");
+        }
+        tokens.push(result.to_string());
+        pgn.push_str(&wrap_tokens(&tokens));
+        pgn.push('\n');
+        pgn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::play;
+    use crate::{PgnGame, PgnReader};
+    use super::*;
+
+    #[test]
+    fn test_export_finished_game() {
+        let mut board = ChessBoardState::new();
+        play(&mut board, &["f3", "e5", "g4", "Qh4#"]);
+        let pgn = board.to_pgn(&[("White", "Fool"), ("Black", "Master"), ("Annotator", "Say \"hi\"")]);
+        assert_eq!(pgn, "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"Fool\"]\n[Black \"Master\"]\n[Result \"0-1\"]\n[Annotator \"Say \\\"hi\\\"\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n");
+    }
+
+    #[test]
+    fn test_export_from_position() {
+        let mut board = ChessBoardState::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 12").unwrap();
+        play(&mut board, &["Kd7", "e4"]);
+        let pgn = board.to_pgn(&[]);
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n"));
+        assert!(pgn.ends_with("\n\n12... Kd7 13. e4 *\n"));
+    }
+
+    #[test]
+    fn test_export_from_start_fen() {
+        let mut board = ChessBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        play(&mut board, &["e4", "e5"]);
+        let pgn = board.to_pgn(&[]);
+        assert!(!pgn.contains("SetUp"));
+        assert!(!pgn.contains("FEN"));
+        assert_eq!(pgn, ChessBoardState::new().to_pgn(&[]).replace("\n\n*", "\n\n1. e4 e5 *"));
+    }
+
+    #[test]
+    fn test_line_wrapping_and_round_trip() {
+        let mut board = ChessBoardState::new();
+        play(&mut board, &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7", "Re1", "b5", "Bb3", "d6", "c3", "O-O", "h3", "Nb8", "d4", "Nbd7", "c4", "c6", "cxb5", "axb5", "Nc3", "Bb7"]);
+        let pgn = board.to_pgn(&[("Event", "Wrapping")]);
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= 80));
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(games[0].moves, board.move_history());
+        assert_eq!(games[0].board.to_fen(), board.to_fen());
+        assert_eq!(games[0].tag("Event"), Some("Wrapping"));
+    }
+}
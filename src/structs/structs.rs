@@ -1,6 +1,7 @@
-use crate::{ChessColors, ChessPieces};
+use crate::{ChessBoardState, ChessColors, ChessPieces};
+use std::fmt;
 
-#[derive( Clone, Copy)]
+#[derive( Clone, Copy, Debug, PartialEq, Eq)]
 /// For a piece on the board, this struct contains the piece type, the color and the position.
 pub struct PositionedChessPiece {
     pub piece: ChessPieces,
@@ -8,14 +9,16 @@ pub struct PositionedChessPiece {
     pub position: ChessBoardPosition,
 }
 
-#[derive( Clone, Copy)]
+/// Tracks for one side whether the king or either of the rooks has moved, which determines the remaining castling rights.
+#[derive( Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CastlingStateData {
     pub rook_a_moved: bool,
     pub rook_h_moved: bool,
     pub king_moved: bool,
 }
 
-#[derive( Clone, Copy)]
+/// A move of a single piece, with `promotion` set for pawns reaching the last row.
+#[derive( Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ChessMove {
     pub from: ChessBoardPosition,
     pub to: ChessBoardPosition,
@@ -24,18 +27,99 @@ pub struct ChessMove {
     pub color: ChessColors,
 }
 
-use std::fmt;
+impl ChessMove {
+    /// Castling is represented as a move of the king by two columns.
+    pub fn is_castling(&self) -> bool {
+        self.piece == ChessPieces::King && (self.to.column as i32 - self.from.column as i32).abs() == 2
+    }
+}
+
+
+/// What happened when a move was performed.
+#[derive( Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveOutcome {
+    /// The piece that was taken, with the square it stood on.
+    pub captured: Option<PositionedChessPiece>,
+    /// The opponent is in check after the move.
+    pub is_check: bool,
+    /// The opponent is checkmated by the move.
+    pub is_checkmate: bool,
+    pub is_castling: bool,
+    pub is_en_passant: bool,
+    /// The piece a pawn was promoted to.
+    pub promotion: Option<ChessPieces>,
+}
 
 /// The position of a piece on the chess board. The row is a number between 0 and 7, the column is a number between 0 and 7.
-#[derive( PartialEq, Eq, Clone, Copy)]
+#[derive( PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub struct ChessBoardPosition {
     pub row: u8,
     pub column: u8,
 }
 
+/// The 781 random numbers of the Zobrist keys, laid out like the Polyglot Random64 table.
+#[derive( Clone, PartialEq, Eq)]
+pub struct ZobristKeys {
+    pub(crate) keys: [u64; 781],
+}
+
+/// A game read from Portable Game Notation, keeping only the mainline.
+#[derive( Clone, Debug)]
+pub struct PgnGame {
+    /// The tag pairs in the order they appear in the input.
+    pub tags: Vec<(String, String)>,
+    /// The moves of the mainline, starting from the position given by the FEN tag or the standard starting position.
+    pub moves: Vec<ChessMove>,
+    /// The result token ("1-0", "0-1", "1/2-1/2" or "*") that terminated the movetext, if any.
+    pub result: Option<String>,
+    /// The position after the last move of the mainline.
+    pub board: ChessBoardState,
+}
+
+impl PgnGame {
+    /// The value of the first tag with the given name.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag_name, _)| tag_name == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Writes the move in UCI long algebraic notation, e.g. "e2e4" or "e7e8q".
+impl fmt::Display for ChessMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            Some(ChessPieces::Queen) => write!(f, "q"),
+            Some(ChessPieces::Rook) => write!(f, "r"),
+            Some(ChessPieces::Bishop) => write!(f, "b"),
+            Some(ChessPieces::Knight) => write!(f, "n"),
+            Some(ChessPieces::King) => write!(f, "k"),
+            Some(ChessPieces::Pawn) => write!(f, "p"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ChessBoardPosition {
+    /// Parses a square like "e4", returning None for anything else.
+    pub fn from_algebraic(name: &str) -> Option<ChessBoardPosition> {
+        let bytes = name.as_bytes();
+        if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1]) {
+            return None;
+        }
+        Some(ChessBoardPosition { row: bytes[1] - b'1', column: bytes[0] - b'a' })
+    }
+}
+
 impl fmt::Display for ChessBoardPosition {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}", (('a' as u8) + self.column) as char, self.row + 1)
+        write!(f, "{}{}", b'a'.saturating_add(self.column) as char, self.row as u16 + 1)
+    }
+}
+
+/// The table is not printed, it would fill the output of every board that is debug printed.
+impl fmt::Debug for ZobristKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ZobristKeys").finish_non_exhaustive()
     }
 }
 
@@ -50,4 +134,26 @@ mod tests {
         let pos2 = ChessBoardPosition { row: 7, column: 7 };
         assert_eq!(format!("{}", pos2), "h8");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_move_display() {
+        let next_move = ChessMove {
+            from: ChessBoardPosition { row: 6, column: 4 },
+            to: ChessBoardPosition { row: 7, column: 4 },
+            promotion: Some(ChessPieces::Knight),
+            piece: ChessPieces::Pawn,
+            color: ChessColors::White,
+        };
+        assert_eq!(next_move.to_string(), "e7e8n");
+        assert_eq!(ChessMove { promotion: None, ..next_move }.to_string(), "e7e8");
+    }
+
+    #[test]
+    fn test_from_algebraic() {
+        assert_eq!(ChessBoardPosition::from_algebraic("e4"), Some(ChessBoardPosition { row: 3, column: 4 }));
+        assert_eq!(ChessBoardPosition::from_algebraic("h8"), Some(ChessBoardPosition { row: 7, column: 7 }));
+        assert_eq!(ChessBoardPosition::from_algebraic("i1"), None);
+        assert_eq!(ChessBoardPosition::from_algebraic("a9"), None);
+        assert_eq!(ChessBoardPosition::from_algebraic("a10"), None);
+    }
+}
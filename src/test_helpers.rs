@@ -0,0 +1,20 @@
+use crate::{ChessBoardPosition, ChessBoardState, ChessColors, ChessMove, ChessPieces};
+
+/// Parses a square like "e4", panicking on anything else.
+pub(crate) fn square(name: &str) -> ChessBoardPosition {
+    ChessBoardPosition::from_algebraic(name).unwrap()
+}
+
+/// A move without promotion between two squares given like "e2" and "e4".
+pub(crate) fn chess_move(piece: ChessPieces, color: ChessColors, from: &str, to: &str) -> ChessMove {
+    ChessMove { from: square(from), to: square(to), promotion: None, piece, color }
+}
+
+/// Performs the moves given in SAN and checks the incremental Zobrist key after each of them.
+pub(crate) fn play(board: &mut ChessBoardState, moves: &[&str]) {
+    for san in moves {
+        let next_move = board.parse_san(san).unwrap();
+        assert!(board.perform_move(next_move).is_ok(), "{}", san);
+        assert_eq!(board.zobrist_key(), board.compute_zobrist_key(), "after {}", san);
+    }
+}
@@ -0,0 +1,85 @@
+//! Perft regression suite: the number of leaf nodes of the move tree, compared with published results for
+//! positions that cover castling, en passant, promotions and the rules around check.
+//!
+//! By default, only the depths up to a couple of hundred thousand nodes are run. Enable the `deep-perft`
+//! feature to run all depths, preferably in release mode: `cargo test --release --features deep-perft`.
+
+use chess_library::ChessBoardState;
+
+const DEEP: bool = cfg!(feature = "deep-perft");
+const MAX_NODES: u64 = 200_000;
+
+/// Runs perft for every depth whose expected node count is within the limit, or for all depths in deep mode. The counts start at depth 1.
+fn check_perft(fen: &str, expected: &[u64]) {
+    let board = ChessBoardState::from_fen(fen).unwrap();
+    for (depth, &nodes) in (1..).zip(expected) {
+        if DEEP || nodes <= MAX_NODES {
+            assert_eq!(board.perft(depth), nodes, "perft({}) of {}", depth, fen);
+        }
+    }
+}
+
+#[test]
+fn test_start_position() {
+    check_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8_902, 197_281, 4_865_609, 119_060_324]);
+}
+
+#[test]
+fn test_kiwipete() {
+    check_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2_039, 97_862, 4_085_603, 193_690_690]);
+}
+
+#[test]
+fn test_position_3() {
+    check_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2_812, 43_238, 674_624, 11_030_083]);
+}
+
+#[test]
+fn test_position_4() {
+    check_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9_467, 422_333, 15_833_292]);
+    check_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9_467, 422_333, 15_833_292]);
+}
+
+#[test]
+fn test_position_5() {
+    check_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1_486, 62_379, 2_103_487, 89_941_194]);
+}
+
+#[test]
+fn test_position_6() {
+    check_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2_079, 89_890, 3_894_594, 164_075_551]);
+}
+
+#[test]
+fn test_en_passant() {
+    // En passant captures that would expose the own king, or that give check.
+    check_perft("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[18, 92, 1_670, 10_138, 185_429, 1_134_888]);
+    check_perft("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", &[13, 102, 1_266, 10_276, 135_655, 1_015_133]);
+    check_perft("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", &[15, 126, 1_928, 13_931, 206_379, 1_440_467]);
+}
+
+#[test]
+fn test_castling() {
+    // Castling that gives check, castling rights lost by rook moves and captures, castling prevented by attacked squares.
+    check_perft("5k2/8/8/8/8/8/8/4K2R w K - 0 1", &[15, 66, 1_198, 6_399, 120_330, 661_072]);
+    check_perft("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", &[16, 71, 1_286, 7_418, 141_077, 803_711]);
+    check_perft("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", &[26, 1_141, 27_826, 1_274_206]);
+    check_perft("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", &[44, 1_494, 50_509, 1_720_476]);
+}
+
+#[test]
+fn test_promotions() {
+    // Promoting out of check, promoting and underpromoting to give check.
+    check_perft("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", &[11, 133, 1_442, 19_174, 266_199, 3_821_001]);
+    check_perft("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", &[9, 40, 472, 2_661, 38_983, 217_342]);
+    check_perft("8/P1k5/K7/8/8/8/8/8 w - - 0 1", &[6, 27, 273, 1_329, 18_135, 92_683]);
+}
+
+#[test]
+fn test_checks_and_game_end() {
+    // Discovered check, self stalemate, and positions with stalemates and checkmates in the tree.
+    check_perft("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", &[29, 165, 5_160, 31_961, 1_004_658]);
+    check_perft("K1k5/8/P7/8/8/8/8/8 w - - 0 1", &[2, 6, 13, 63, 382, 2_217]);
+    check_perft("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", &[10, 25, 268, 926, 10_857, 43_261, 567_584]);
+    check_perft("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", &[37, 183, 6_559, 23_527]);
+}